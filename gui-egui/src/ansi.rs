@@ -0,0 +1,185 @@
+use eframe::egui::Color32;
+
+/// A run of text sharing one SGR style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsiSpan {
+    pub text: String,
+    /// `None` means the terminal's default foreground.
+    pub color: Option<Color32>,
+    pub bold: bool,
+}
+
+const BASIC: [Color32; 8] = [
+    Color32::from_rgb(0, 0, 0),
+    Color32::from_rgb(205, 49, 49),
+    Color32::from_rgb(13, 188, 121),
+    Color32::from_rgb(229, 229, 16),
+    Color32::from_rgb(36, 114, 200),
+    Color32::from_rgb(188, 63, 188),
+    Color32::from_rgb(17, 168, 205),
+    Color32::from_rgb(229, 229, 229),
+];
+
+const BRIGHT: [Color32; 8] = [
+    Color32::from_rgb(102, 102, 102),
+    Color32::from_rgb(241, 76, 76),
+    Color32::from_rgb(35, 209, 139),
+    Color32::from_rgb(245, 245, 67),
+    Color32::from_rgb(59, 142, 234),
+    Color32::from_rgb(214, 112, 214),
+    Color32::from_rgb(41, 184, 219),
+    Color32::from_rgb(255, 255, 255),
+];
+
+/// Maps a 256-color palette index to RGB.
+fn color_256(n: u8) -> Color32 {
+    match n {
+        0..=7 => BASIC[n as usize],
+        8..=15 => BRIGHT[n as usize - 8],
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color32::from_rgb(level(n / 36), level((n / 6) % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n - 232) * 10;
+            Color32::from_rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// Applies one SGR parameter list (the part between `ESC[` and `m`).
+fn apply_sgr(params: &str, color: &mut Option<Color32>, bold: &mut bool) {
+    let codes: Vec<u8> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => { *color = None; *bold = false; }
+            1 => *bold = true,
+            22 => *bold = false,
+            c @ 30..=37 => *color = Some(BASIC[(c - 30) as usize]),
+            c @ 90..=97 => *color = Some(BRIGHT[(c - 90) as usize]),
+            39 => *color = None,
+            38 => match codes.get(i + 1) {
+                Some(5) => {
+                    *color = codes.get(i + 2).map(|&n| color_256(n));
+                    i += 2;
+                }
+                Some(2) => {
+                    if let (Some(&r), Some(&g), Some(&b)) = (codes.get(i + 2), codes.get(i + 3), codes.get(i + 4)) {
+                        *color = Some(Color32::from_rgb(r, g, b));
+                    }
+                    i += 4;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Splits a line containing ANSI escapes into styled spans. SGR color/bold codes are
+/// honoured; any other escape sequence is dropped.
+pub fn parse_ansi(line: &str) -> Vec<AnsiSpan> {
+    let mut spans = Vec::new();
+    let mut color = None;
+    let mut bold = false;
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            text.push(c);
+            continue;
+        }
+        if chars.peek() != Some(&'[') {
+            // Not a CSI sequence: skip the escape and the byte that follows it.
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let mut params = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() || c == '~' {
+                terminator = Some(c);
+                break;
+            }
+            params.push(c);
+        }
+        if terminator == Some('m') {
+            if !text.is_empty() {
+                spans.push(AnsiSpan { text: std::mem::take(&mut text), color, bold });
+            }
+            apply_sgr(&params, &mut color, &mut bold);
+        }
+    }
+    if !text.is_empty() {
+        spans.push(AnsiSpan { text, color, bold });
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, color: Option<Color32>, bold: bool) -> AnsiSpan {
+        AnsiSpan { text: text.to_string(), color, bold }
+    }
+
+    #[test]
+    fn plain_text_is_one_default_span() {
+        assert_eq!(parse_ansi("ok: [web1]"), vec![span("ok: [web1]", None, false)]);
+        assert!(parse_ansi("").is_empty());
+    }
+
+    #[test]
+    fn reset_clears_color_and_bold() {
+        assert_eq!(parse_ansi("\x1b[1;31mfatal\x1b[0m done"), vec![
+            span("fatal", Some(BASIC[1]), true),
+            span(" done", None, false),
+        ]);
+        // An empty parameter list is a reset too.
+        assert_eq!(parse_ansi("\x1b[1;33mwarn\x1b[m done")[1], span(" done", None, false));
+    }
+
+    #[test]
+    fn sixteen_colors_and_bold() {
+        assert_eq!(parse_ansi("\x1b[32mgreen\x1b[92mbright\x1b[39mdefault"), vec![
+            span("green", Some(BASIC[2]), false),
+            span("bright", Some(BRIGHT[2]), false),
+            span("default", None, false),
+        ]);
+        assert_eq!(parse_ansi("\x1b[1mbold\x1b[22mnormal"), vec![span("bold", None, true), span("normal", None, false)]);
+    }
+
+    #[test]
+    fn palette_and_truecolor() {
+        assert_eq!(parse_ansi("\x1b[38;5;196mred")[0].color, Some(Color32::from_rgb(255, 0, 0)));
+        assert_eq!(parse_ansi("\x1b[38;5;9mbright")[0].color, Some(BRIGHT[1]));
+        assert_eq!(parse_ansi("\x1b[38;5;244mgray")[0].color, Some(Color32::from_rgb(128, 128, 128)));
+        assert_eq!(parse_ansi("\x1b[38;2;10;20;30mrgb")[0].color, Some(Color32::from_rgb(10, 20, 30)));
+        // Codes after an extended color still apply.
+        assert_eq!(parse_ansi("\x1b[38;5;2;1mx"), vec![span("x", Some(BASIC[2]), true)]);
+    }
+
+    #[test]
+    fn unknown_and_truncated_sequences_are_dropped() {
+        assert_eq!(parse_ansi("\x1b[4munderlined\x1b[2K"), vec![span("underlined", None, false)]);
+        assert_eq!(parse_ansi("\x1b[31m\x1b[38;5mx")[0].color, None);
+        assert_eq!(parse_ansi("\x1b[31m\x1b[38;2;1;2mx")[0].color, Some(BASIC[1]));
+        assert_eq!(parse_ansi("text\x1b[31"), vec![span("text", None, false)]);
+        assert_eq!(parse_ansi("a\x1b7b"), vec![span("ab", None, false)]);
+    }
+
+    #[test]
+    fn text_split_across_escapes_keeps_each_style() {
+        assert_eq!(parse_ansi("\x1b[0;32mok: \x1b[1m[web1]\x1b[22m done\x1b[0m"), vec![
+            span("ok: ", Some(BASIC[2]), false),
+            span("[web1]", Some(BASIC[2]), true),
+            span(" done", Some(BASIC[2]), false),
+        ]);
+    }
+}
@@ -0,0 +1,582 @@
+use crate::config::{expand_tilde, ProvisioningConfig};
+use std::fmt::Display;
+use std::path::Path;
+
+/// Environment variable carrying the sudo password to ansible, so it never appears in argv.
+pub const BECOME_PASS_ENV: &str = "RUSTSIBLE_BECOME_PASS";
+/// Environment variable carrying the created admin user's password.
+pub const USER_PASS_ENV: &str = "RUSTSIBLE_USER_PASS";
+/// Environment variable carrying the SSH connection password.
+pub const CONN_PASS_ENV: &str = "RUSTSIBLE_CONN_PASS";
+/// Where ansible keeps this run's SSH multiplexing sockets.
+pub const CONTROL_PATH_DIR_ENV: &str = "ANSIBLE_SSH_CONTROL_PATH_DIR";
+
+/// Environment variables the GUI controls itself: the color mode its output parsing relies
+/// on and the secret-carrying variables. User-defined values for these are refused.
+pub const RESERVED_ENV_VARS: [&str; 7] = ["ANSIBLE_NOCOLOR", "ANSIBLE_FORCE_COLOR", "NO_COLOR", "FORCE_COLOR", BECOME_PASS_ENV, USER_PASS_ENV, CONN_PASS_ENV];
+
+/// Extra-var value that makes ansible read `var` from its environment.
+fn env_lookup(var: &str) -> String {
+    format!("{{{{ lookup('env', '{}') }}}}", var)
+}
+
+fn bool_to_yes_no(v: bool) -> &'static str { if v { "yes" } else { "no" } }
+
+/// Extra vars collected for the single `-e` JSON object `build_ansible_args` passes.
+pub type ExtraVars = serde_json::Map<String, serde_json::Value>;
+
+/// Sets `key` to `value` as a string, like ansible's own `key=value` parsing would.
+fn push_var(vars: &mut ExtraVars, key: &str, value: impl Display) {
+    vars.insert(key.to_string(), value.to_string().into());
+}
+
+/// Environment variables set on the `ansible-playbook` child: the user's `env_vars`, then
+/// the GUI's own, including the secret-carrying `CONN_PASS_ENV`, `BECOME_PASS_ENV` and
+/// `USER_PASS_ENV`.
+pub fn ansible_env(config: &ProvisioningConfig, control_path_dir: &Path) -> Vec<(String, String)> {
+    let mut env = galaxy_env(config);
+    let mut set = |key: &str, value: String| env.push((key.to_string(), value));
+    // Environment settings beat ansible.cfg, so a custom config can't change the color
+    // mode the output parser depends on. stdout is a pipe, so raw mode has to force color.
+    if config.raw_output {
+        set("ANSIBLE_FORCE_COLOR", "1".to_string());
+    } else {
+        set("ANSIBLE_NOCOLOR", "1".to_string());
+    }
+    let mut ssh_args = if config.ssh_multiplexing {
+        "-C -o ControlMaster=auto -o ControlPersist=60s".to_string()
+    } else {
+        "-o ControlMaster=no -o ControlPath=none".to_string()
+    };
+    if let Some(jump) = config.proxy_jump() {
+        ssh_args.push_str(&format!(" -o ProxyJump={}", jump));
+    }
+    set("ANSIBLE_SSH_ARGS", ssh_args);
+    if config.ssh_multiplexing {
+        set(CONTROL_PATH_DIR_ENV, control_path_dir.display().to_string());
+    }
+    set("ANSIBLE_SSH_RETRIES", config.connection_retries.to_string());
+    if !config.connection_password.is_empty() {
+        set(CONN_PASS_ENV, config.connection_password.clone());
+    }
+    if config.needs_become() && !config.become_password.is_empty() {
+        set(BECOME_PASS_ENV, config.become_password.clone());
+    }
+    if config.create_user && !config.user_password.is_empty() {
+        set(USER_PASS_ENV, config.user_password.clone());
+    }
+    env
+}
+
+/// `ANSIBLE_ROLES_PATH` / `ANSIBLE_COLLECTIONS_PATH` for the configured, colon-separated
+/// paths, with `~` expanded in each.
+fn content_paths_env(config: &ProvisioningConfig) -> Vec<(&'static str, String)> {
+    [("ANSIBLE_ROLES_PATH", &config.roles_path), ("ANSIBLE_COLLECTIONS_PATH", &config.collections_path)]
+        .into_iter()
+        .filter(|(_, paths)| !paths.trim().is_empty())
+        .map(|(key, paths)| {
+            let expanded: Vec<String> = paths.trim().split(':').filter(|p| !p.is_empty()).map(|p| expand_tilde(p).display().to_string()).collect();
+            (key, expanded.join(":"))
+        })
+        .collect()
+}
+
+/// Environment for `ansible-galaxy`, which `ansible_env` extends for `ansible-playbook`.
+pub fn galaxy_env(config: &ProvisioningConfig) -> Vec<(String, String)> {
+    // User variables come first so the GUI's own settings below win on conflicts.
+    let mut env = user_env_vars(config);
+    if !config.ansible_cfg_path.trim().is_empty() {
+        env.push(("ANSIBLE_CONFIG".to_string(), expand_tilde(&config.ansible_cfg_path).display().to_string()));
+    }
+    env.extend(content_paths_env(config).into_iter().map(|(key, value)| (key.to_string(), value)));
+    env
+}
+
+/// Splits a comma-separated tag field into trimmed, non-empty tags.
+pub fn parse_tag_list(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect()
+}
+
+/// Jinja delimiters refused in free-text values: ansible would template them on the
+/// controller. Whitespace is fine, as values travel in a JSON object.
+const JINJA_DELIMITERS: [&str; 3] = ["{{", "{%", "{#"];
+
+fn is_valid_var_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The user's `env_vars` that pass `validate_env_var`, with trimmed names. Every consumer
+/// goes through this, so the GUI run, `ansible-galaxy` and exported scripts agree.
+pub fn user_env_vars(config: &ProvisioningConfig) -> Vec<(String, String)> {
+    config
+        .env_vars
+        .iter()
+        .filter(|(key, value)| validate_env_var(key, value).is_ok())
+        .map(|(key, value)| (key.trim().to_string(), value.clone()))
+        .collect()
+}
+
+/// Checks a user-defined environment variable: a valid name that isn't reserved by the
+/// GUI, and a single-line value.
+pub fn validate_env_var(key: &str, value: &str) -> Result<(), String> {
+    let key = key.trim();
+    if !is_valid_var_name(key) {
+        return Err(format!("'{}' is not a valid environment variable name", key));
+    }
+    if RESERVED_ENV_VARS.contains(&key) {
+        return Err(format!("{} is managed by Rustsible and can't be overridden", key));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(format!("{}: value contains a newline or control character", key));
+    }
+    Ok(())
+}
+
+/// Checks a single extra-var value for control characters and Jinja delimiters.
+pub fn validate_var_value(key: &str, value: &str) -> Result<(), String> {
+    if value.chars().any(char::is_control) {
+        return Err(format!("{}: value contains a newline or control character", key));
+    }
+    if let Some(delimiter) = JINJA_DELIMITERS.iter().find(|d| value.contains(*d)) {
+        return Err(format!("{}: value contains the template delimiter '{}'", key, delimiter));
+    }
+    Ok(())
+}
+
+/// Validates the free-text config fields that become extra vars.
+///
+/// Passwords travel through the environment and aren't templated, so they are only
+/// checked for control characters.
+pub fn validate_arg_values(config: &ProvisioningConfig) -> Result<(), String> {
+    for host in config.target_hosts() {
+        validate_var_value("target_ip", &host)?;
+    }
+    validate_var_value("target_user", config.ssh_user.trim())?;
+    validate_var_value("jump_user", config.jump_user.trim())?;
+    validate_var_value("target_hostname", config.hostname.trim())?;
+    validate_var_value("ansible_python_interpreter", config.ansible_python_interpreter.trim())?;
+    if config.ansible_python_interpreter.trim().contains(char::is_whitespace) {
+        return Err("ansible_python_interpreter: must be a single path without spaces".to_string());
+    }
+    validate_var_value("added_user", config.added_user.trim())?;
+    for tag in config.tags.iter().chain(&config.skip_tags) {
+        validate_var_value("tags", tag)?;
+    }
+    let passwords = [
+        ("connection_password", &config.connection_password),
+        ("become_password", &config.become_password),
+        ("user_password", &config.user_password),
+    ];
+    for (key, secret) in passwords {
+        if secret.chars().any(char::is_control) {
+            return Err(format!("{}: value contains a newline or control character", key));
+        }
+    }
+    Ok(())
+}
+
+/// Runs `validate_var_value` on every string inside a JSON extra var, nested ones included.
+fn validate_json_value(key: &str, value: &serde_json::Value) -> Result<(), String> {
+    match value {
+        serde_json::Value::String(s) => validate_var_value(key, s),
+        serde_json::Value::Array(items) => items.iter().try_for_each(|item| validate_json_value(key, item)),
+        serde_json::Value::Object(fields) => fields.values().try_for_each(|field| validate_json_value(key, field)),
+        _ => Ok(()),
+    }
+}
+
+/// Parses the free-text extra vars: either one JSON object, or one `key=value` per line
+/// (blank lines and `#` comments ignored) whose values are taken whole, spaces included.
+pub fn parse_extra_vars(raw: &str) -> Result<ExtraVars, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(ExtraVars::new());
+    }
+    if trimmed.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(trimmed)
+            .map_err(|e| format!("Extra vars JSON is invalid: {}", e))?;
+        let serde_json::Value::Object(vars) = value else {
+            return Err("Extra vars JSON must be an object".to_string());
+        };
+        for (key, value) in &vars {
+            validate_json_value(key, value)?;
+        }
+        return Ok(vars);
+    }
+
+    let mut vars = ExtraVars::new();
+    for (i, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("Line {}: expected key=value", i + 1));
+        };
+        let key = key.trim();
+        if !is_valid_var_name(key) {
+            return Err(format!("Line {}: '{}' is not a valid variable name", i + 1, key));
+        }
+        let value = value.trim();
+        validate_var_value(key, value).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        push_var(&mut vars, key, value);
+    }
+    Ok(vars)
+}
+
+/// Builds the full `ansible-playbook` argument vector for a config: the playbook, an
+/// optional `-i` inventory, then every variable in one `-e` JSON object, which ansible
+/// never splits on whitespace.
+pub fn build_ansible_args(config: &ProvisioningConfig) -> Vec<String> {
+    let mut args = Vec::new();
+
+    let playbook = config.playbook_path.trim();
+    args.push(if playbook.is_empty() { "playbook.yml".to_string() } else { expand_tilde(playbook).display().to_string() });
+    if !config.inventory_path.trim().is_empty() {
+        args.push("-i".to_string());
+        args.push(expand_tilde(&config.inventory_path).display().to_string());
+    }
+    if config.check_mode {
+        args.push("--check".to_string());
+        args.push("--diff".to_string());
+    }
+    if !config.limit.is_empty() {
+        args.push("--limit".to_string());
+        args.push(config.limit.clone());
+    }
+    if !config.tags.is_empty() {
+        args.push("--tags".to_string());
+        args.push(config.tags.join(","));
+    }
+    if !config.skip_tags.is_empty() {
+        args.push("--skip-tags".to_string());
+        args.push(config.skip_tags.join(","));
+    }
+
+    args.push("--timeout".to_string());
+    args.push(config.connect_timeout.to_string());
+    args.push("--forks".to_string());
+    args.push(config.forks.to_string());
+
+    let mut vars = ExtraVars::new();
+    if config.needs_become() {
+        args.push("--become".to_string());
+        if !config.become_password.is_empty() {
+            push_var(&mut vars, "ansible_become_pass", env_lookup(BECOME_PASS_ENV));
+        }
+    }
+
+    push_var(&mut vars, "target_ip", config.target_hosts().join(","));
+    push_var(&mut vars, "target_port", config.ssh_port);
+    push_var(&mut vars, "target_user", &config.ssh_user);
+    // Per-host `user@host:port` overrides, applied by add_host in Play 1.
+    let overrides: serde_json::Map<String, serde_json::Value> = config
+        .host_entries()
+        .into_iter()
+        .filter(|e| e.user.is_some() || e.port.is_some())
+        .map(|e| {
+            let mut vars = serde_json::Map::new();
+            if let Some(user) = e.user { vars.insert("user".to_string(), user.into()); }
+            if let Some(port) = e.port { vars.insert("port".to_string(), port.into()); }
+            (e.host, vars.into())
+        })
+        .collect();
+    if !overrides.is_empty() {
+        vars.insert("target_host_overrides".to_string(), overrides.into());
+    }
+    // Set on the targets by add_host; as ansible_python_interpreter it would also apply to
+    // the localhost play.
+    if !config.ansible_python_interpreter.trim().is_empty() {
+        push_var(&mut vars, "target_python_interpreter", config.ansible_python_interpreter.trim());
+    }
+    if config.offline_mode { push_var(&mut vars, "offline_mode", "yes"); }
+    if !config.connection_password.is_empty() { push_var(&mut vars, "connection_password", env_lookup(CONN_PASS_ENV)); }
+    // An empty key path is omitted by the playbook so ansible falls back to ansible_password.
+    push_var(&mut vars, "ssh_key_path", expand_tilde(&config.ssh_key_path).display());
+    
+    if !config.hostname.is_empty() { push_var(&mut vars, "target_hostname", &config.hostname); }
+    push_var(&mut vars, "prompt_create_user", bool_to_yes_no(config.create_user));
+    if config.create_user {
+        push_var(&mut vars, "added_user", config.added_user.trim());
+        if !config.user_password.is_empty() {
+            push_var(&mut vars, "user_password", env_lookup(USER_PASS_ENV));
+        }
+    }
+    push_var(&mut vars, "prompt_install_docker", bool_to_yes_no(config.docker));
+    push_var(&mut vars, "prompt_install_lemp", bool_to_yes_no(config.lemp));
+    push_var(&mut vars, "prompt_install_wordpress", bool_to_yes_no(config.wordpress));
+    push_var(&mut vars, "prompt_install_certbot", bool_to_yes_no(config.certbot));
+    push_var(&mut vars, "prompt_install_dev_tools", bool_to_yes_no(config.devtools));
+    for (var, enabled) in config.devtool_flags() {
+        push_var(&mut vars, var, bool_to_yes_no(enabled));
+    }
+    push_var(&mut vars, "prompt_enable_fail2ban", bool_to_yes_no(config.fail2ban));
+    if config.fail2ban {
+        for (var, value) in config.fail2ban_settings() {
+            push_var(&mut vars, var, value);
+        }
+    }
+    push_var(&mut vars, "prompt_enable_swap", bool_to_yes_no(config.swap));
+    // Extra vars beat the role's set_fact, so the size is only passed when it's explicit.
+    if config.swap && config.swap_size_mb > 0 { push_var(&mut vars, "swap_size_mb", config.swap_size_mb); }
+    push_var(&mut vars, "prompt_enable_cron_jobs", bool_to_yes_no(config.cron_jobs));
+    push_var(&mut vars, "prompt_enable_periodic_reboot", bool_to_yes_no(config.periodic_reboot));
+    let (reboot_hour, reboot_minute) = config.reboot_schedule();
+    push_var(&mut vars, "cron_reboot_hour", reboot_hour);
+    push_var(&mut vars, "cron_reboot_minute", reboot_minute);
+
+    if config.system_hardening { push_var(&mut vars, "enable_kernel_hardening", "yes"); }
+    if config.apparmor { push_var(&mut vars, "enable_apparmor", "yes"); }
+    if config.rootkit_detection { push_var(&mut vars, "enable_rkhunter", "yes"); }
+    if config.file_integrity { push_var(&mut vars, "enable_aide", "yes"); }
+    if config.audit_logging { push_var(&mut vars, "enable_auditd", "yes"); }
+    if config.log_monitoring { push_var(&mut vars, "enable_logwatch", "yes"); }
+    
+    if config.advanced_protection { push_var(&mut vars, "enable_ssh_2fa", "yes"); }
+    
+    if config.backups { push_var(&mut vars, "enable_backups", "yes"); }
+    if config.usb_restrictions { push_var(&mut vars, "enable_usb_restrictions", "yes"); }
+    if config.disable_ipv6 { push_var(&mut vars, "disable_ipv6", "yes"); }
+    if config.suricata { push_var(&mut vars, "enable_suricata", "yes"); }
+
+    // Free-text vars go last so they override the structured ones; invalid input is
+    // rejected before launch, so it's simply skipped here.
+    vars.extend(parse_extra_vars(&config.extra_vars_raw).unwrap_or_default());
+    args.push("-e".to_string());
+    args.push(serde_json::Value::Object(vars).to_string());
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The argv before the extra vars, and the extra vars JSON object.
+    fn split_args(args: &[String]) -> (&[String], serde_json::Value) {
+        let (json, head) = args.split_last().expect("extra vars argument");
+        assert_eq!(head.last().map(String::as_str), Some("-e"));
+        (&head[..head.len() - 1], serde_json::from_str(json).expect("extra vars are JSON"))
+    }
+
+    /// A JSON object of string values.
+    fn object(pairs: &[(&str, &str)]) -> serde_json::Value {
+        pairs.iter().map(|(k, v)| (k.to_string(), serde_json::Value::from(*v))).collect::<ExtraVars>().into()
+    }
+
+    fn base_config() -> ProvisioningConfig {
+        ProvisioningConfig {
+            ip_address: "203.0.113.10".to_string(),
+            ssh_key_path: "/keys/id_ed25519".to_string(),
+            ..ProvisioningConfig::default()
+        }
+    }
+
+    #[test]
+    fn default_config_args() {
+        let args = build_ansible_args(&base_config());
+        let (head, vars) = split_args(&args);
+        assert_eq!(head, ["playbook.yml", "--timeout", "30", "--forks", "5"]);
+        assert_eq!(vars, object(&[
+            ("added_user", "organic"),
+            ("cron_reboot_hour", "3"),
+            ("cron_reboot_minute", "1"),
+            ("install_autossh", "yes"), ("install_bat", "yes"), ("install_btop", "yes"), ("install_claude_code", "yes"),
+            ("install_direnv", "yes"), ("install_duf", "yes"), ("install_eza", "yes"), ("install_fd", "yes"),
+            ("install_fish", "no"), ("install_fzf", "yes"), ("install_gemini", "no"), ("install_github_cli", "yes"),
+            ("install_gping", "yes"), ("install_htop", "yes"), ("install_jq", "yes"), ("install_kiro", "no"),
+            ("install_lazygit", "yes"), ("install_lnav", "yes"), ("install_micro", "yes"), ("install_ncdu", "yes"),
+            ("install_neovim", "yes"), ("install_nmap", "yes"), ("install_nodejs", "yes"), ("install_ranger", "yes"),
+            ("install_ripgrep", "yes"), ("install_starship", "yes"), ("install_tldr", "yes"), ("install_tmux", "yes"),
+            ("install_uv", "no"), ("install_zoxide", "yes"), ("install_zsh", "yes"),
+            ("prompt_create_user", "yes"),
+            ("prompt_enable_cron_jobs", "yes"),
+            ("prompt_enable_fail2ban", "yes"),
+            ("prompt_enable_periodic_reboot", "no"),
+            ("prompt_enable_swap", "yes"),
+            ("prompt_install_certbot", "no"),
+            ("prompt_install_dev_tools", "yes"),
+            ("prompt_install_docker", "yes"),
+            ("prompt_install_lemp", "no"),
+            ("prompt_install_wordpress", "no"),
+            ("ssh_key_path", "/keys/id_ed25519"),
+            ("target_ip", "203.0.113.10"),
+            ("target_port", "22"),
+            ("target_python_interpreter", "auto"),
+            ("target_user", "root"),
+        ]));
+    }
+
+    #[test]
+    fn security_options_add_exactly_their_vars() {
+        let config = ProvisioningConfig {
+            system_hardening: true,
+            apparmor: true,
+            rootkit_detection: true,
+            file_integrity: true,
+            audit_logging: true,
+            log_monitoring: true,
+            advanced_protection: true,
+            backups: true,
+            usb_restrictions: true,
+            disable_ipv6: true,
+            suricata: true,
+            ..base_config()
+        };
+        let args = build_ansible_args(&config);
+        let (head, vars) = split_args(&args);
+        assert_eq!(head, ["playbook.yml", "--timeout", "30", "--forks", "5"]);
+
+        let default_args = build_ansible_args(&base_config());
+        let (_, mut expected) = split_args(&default_args);
+        let security = object(&[
+            ("disable_ipv6", "yes"),
+            ("enable_aide", "yes"),
+            ("enable_apparmor", "yes"),
+            ("enable_auditd", "yes"),
+            ("enable_backups", "yes"),
+            ("enable_kernel_hardening", "yes"),
+            ("enable_logwatch", "yes"),
+            ("enable_rkhunter", "yes"),
+            ("enable_ssh_2fa", "yes"),
+            ("enable_suricata", "yes"),
+            ("enable_usb_restrictions", "yes"),
+        ]);
+        expected.as_object_mut().unwrap().extend(security.as_object().unwrap().clone());
+        assert_eq!(vars, expected);
+    }
+
+    #[test]
+    fn emitted_reboot_schedule_matches_the_ui() {
+        for (stored_hour, stored_minute, hour, minute) in [
+            ("3", "1", "3", "1"),
+            (" 5 ", "30", "5", "30"),
+            ("*/6", "0", "*/6", "0"),
+            ("27", "75", "23", "59"),
+            ("*/0", "15", "*/1", "15"),
+            ("*/48", "15", "*/23", "15"),
+            ("1-5", "*/10", "1-5", "*/10"),
+            ("garbage", "", "3", "1"),
+        ] {
+            let mut config = ProvisioningConfig {
+                periodic_reboot: true,
+                reboot_hour: stored_hour.to_string(),
+                reboot_minute: stored_minute.to_string(),
+                ..base_config()
+            };
+            let args = build_ansible_args(&config);
+            let (_, vars) = split_args(&args);
+            assert_eq!((vars["cron_reboot_hour"].as_str(), vars["cron_reboot_minute"].as_str()), (Some(hour), Some(minute)), "stored {:?}", (stored_hour, stored_minute));
+            // The Maintenance tab shows `reboot_schedule()`, and normalizing stores it.
+            assert_eq!(config.reboot_schedule(), (hour.to_string(), minute.to_string()));
+            config.normalize_reboot_schedule();
+            assert_eq!((config.reboot_hour.as_str(), config.reboot_minute.as_str()), (hour, minute));
+        }
+    }
+
+    #[test]
+    fn passwords_stay_out_of_argv() {
+        let config = ProvisioningConfig {
+            ssh_user: "deploy".to_string(),
+            use_become: true,
+            connection_password: "hunter2 with spaces".to_string(),
+            become_password: "sudo-secret".to_string(),
+            user_password: "user-secret".to_string(),
+            ..base_config()
+        };
+        let args = build_ansible_args(&config);
+        assert!(args.iter().all(|a| !a.contains("hunter2") && !a.contains("secret")), "{:?}", args);
+        let (head, vars) = split_args(&args);
+        assert!(head.contains(&"--become".to_string()));
+        assert_eq!(vars["connection_password"], env_lookup(CONN_PASS_ENV));
+        assert_eq!(vars["ansible_become_pass"], env_lookup(BECOME_PASS_ENV));
+        assert_eq!(vars["user_password"], env_lookup(USER_PASS_ENV));
+    }
+
+    #[test]
+    fn var_values_with_templates_or_control_characters_are_rejected() {
+        for value in ["{{ lookup('pipe', 'id') }}", "{% if true %}x{% endif %}", "a{#b", "line\nbreak", "tab\there", "nul\0"] {
+            assert!(validate_var_value("target_hostname", value).is_err(), "{:?}", value);
+        }
+        for value in ["web-01", "with spaces", "semi;colon", "$(not a shell)", "{ single brace }", ""] {
+            assert_eq!(validate_var_value("target_hostname", value), Ok(()), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn config_with_a_templated_field_is_rejected() {
+        assert_eq!(validate_arg_values(&base_config()), Ok(()));
+        let hostname = ProvisioningConfig { hostname: "{{ evil }}".to_string(), ..base_config() };
+        assert_eq!(
+            validate_arg_values(&hostname),
+            Err("target_hostname: value contains the template delimiter '{{'".to_string())
+        );
+        let interpreter = ProvisioningConfig { ansible_python_interpreter: "/usr/bin/python3 -c x".to_string(), ..base_config() };
+        assert!(validate_arg_values(&interpreter).is_err());
+        let password = ProvisioningConfig { connection_password: "pass\nword".to_string(), ..base_config() };
+        assert!(validate_arg_values(&password).is_err());
+        let become_password = ProvisioningConfig { become_password: "pass\rword".to_string(), ..base_config() };
+        assert_eq!(
+            validate_arg_values(&become_password),
+            Err("become_password: value contains a newline or control character".to_string())
+        );
+        let user_password = ProvisioningConfig { user_password: "pass\tword".to_string(), ..base_config() };
+        assert!(validate_arg_values(&user_password).is_err());
+        let templated = ProvisioningConfig { become_password: "{{ not templated }}".to_string(), ..base_config() };
+        assert_eq!(validate_arg_values(&templated), Ok(()));
+    }
+
+    #[test]
+    fn bad_extra_var_lines_are_rejected() {
+        assert_eq!(parse_extra_vars("good=1\nno equals sign"), Err("Line 2: expected key=value".to_string()));
+        assert_eq!(
+            parse_extra_vars("# comment\n\n1bad=x"),
+            Err("Line 3: '1bad' is not a valid variable name".to_string())
+        );
+        assert_eq!(
+            parse_extra_vars("motd={{ lookup('env', 'HOME') }}"),
+            Err("Line 1: motd: value contains the template delimiter '{{'".to_string())
+        );
+        assert_eq!(
+            parse_extra_vars(r#"{"x": "{{ lookup('pipe','id') }}"}"#),
+            Err("x: value contains the template delimiter '{{'".to_string())
+        );
+        assert_eq!(
+            parse_extra_vars(r#"{"users": [{"name": "ok", "shell": "{% raw %}"}]}"#),
+            Err("users: value contains the template delimiter '{%'".to_string())
+        );
+        assert!(parse_extra_vars(r#"{"x": "line\nbreak"}"#).is_err());
+        assert!(parse_extra_vars(r#"{"port": 22, "users": ["a", "b"], "opts": {"debug": true}}"#).is_ok());
+        assert!(parse_extra_vars("[1, 2]").is_err());
+        assert!(parse_extra_vars("{ not json").is_err());
+    }
+
+    #[test]
+    fn extra_var_values_with_spaces_stay_whole() {
+        let config = ProvisioningConfig {
+            extra_vars_raw: "motd = Welcome to  the box\ngreeting=a=b c".to_string(),
+            ..base_config()
+        };
+        let (_, vars) = split_args(&build_ansible_args(&config));
+        assert_eq!(vars["motd"], "Welcome to  the box");
+        assert_eq!(vars["greeting"], "a=b c");
+    }
+
+    #[test]
+    fn env_vars_with_bad_values_never_reach_galaxy() {
+        let config = ProvisioningConfig {
+            env_vars: vec![
+                (" HTTP_PROXY ".to_string(), "http://proxy:3128".to_string()),
+                ("INJECTED".to_string(), "x\nANSIBLE_CONFIG=/tmp/evil".to_string()),
+                ("ANSIBLE_NOCOLOR".to_string(), "0".to_string()),
+                (String::new(), String::new()),
+            ],
+            ..base_config()
+        };
+        let expected = vec![("HTTP_PROXY".to_string(), "http://proxy:3128".to_string())];
+        assert_eq!(user_env_vars(&config), expected);
+        assert_eq!(galaxy_env(&config), expected);
+    }
+}
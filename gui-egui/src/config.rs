@@ -1,18 +1,224 @@
+use crate::preflight::validate_cron_field;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Allowed range for `forks`.
+pub const FORKS_RANGE: std::ops::RangeInclusive<u32> = 1..=100;
+/// Allowed explicit swap sizes in MB; 0 (auto) is accepted separately.
+pub const SWAP_SIZE_RANGE_MB: std::ops::RangeInclusive<u32> = 256..=65536;
 
 fn default_true() -> bool {
     true
 }
 
+fn default_ssh_port() -> u16 {
+    22
+}
+
+fn default_max_output_lines() -> usize {
+    5000
+}
+
+fn default_reboot_minute() -> String {
+    "1".to_string()
+}
+
+fn default_python_interpreter() -> String {
+    "auto".to_string()
+}
+
+fn default_log_retention() -> usize {
+    20
+}
+
+fn default_connect_timeout() -> u32 {
+    30
+}
+
+fn default_forks() -> u32 {
+    5
+}
+
+fn default_font_scale() -> f32 {
+    1.0
+}
+
+/// Color scheme; `System` follows the OS light/dark preference.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    System,
+}
+
+/// Largest IPv4 range the host field expands.
+pub const MAX_CIDR_HOSTS: usize = 256;
+
+/// Usable host addresses of an IPv4 `address/prefix` range. Host bits in the address are
+/// ignored; the network and broadcast addresses are skipped except in /31 and /32.
+pub fn expand_cidr(cidr: &str) -> Result<Vec<String>, String> {
+    let (address, prefix) = cidr.split_once('/').ok_or_else(|| format!("{}: expected address/prefix", cidr))?;
+    let address: std::net::Ipv4Addr = address.parse().map_err(|_| format!("{}: only IPv4 ranges can be expanded", cidr))?;
+    let prefix = match prefix.parse::<u32>() {
+        Ok(n) if n <= 32 => n,
+        _ => return Err(format!("{}: prefix length must be between 0 and 32", cidr)),
+    };
+    let size = 1u64 << (32 - prefix);
+    let usable = if size > 2 { size - 2 } else { size };
+    if usable > MAX_CIDR_HOSTS as u64 {
+        return Err(format!("{}: expands to {} hosts, more than the limit of {}", cidr, usable, MAX_CIDR_HOSTS));
+    }
+    let network = u64::from(u32::from(address)) & !(size - 1);
+    let hosts = if size > 2 { network + 1..network + size - 1 } else { network..network + size };
+    Ok(hosts.map(|ip| std::net::Ipv4Addr::from(ip as u32).to_string()).collect())
+}
+
+/// One entry of the target host field: `host`, `user@host`, `host:port` or
+/// `user@[v6-address]:port`, where the host may be an IPv4 `address/prefix` range.
+/// Omitted parts fall back to the global SSH user and port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostEntry {
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+}
+
+impl HostEntry {
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let fail = |msg: &str| Err(format!("{}: {}", entry, msg));
+        let (user, rest) = match entry.split_once('@') {
+            Some((user, rest)) => (Some(user), rest),
+            None => (None, entry),
+        };
+        if let Some(user) = user {
+            if user.is_empty() || user.starts_with('-') || !user.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+                return fail("the user before '@' may only use letters, digits, '.', '_' and '-'");
+            }
+        }
+        let (host, port) = if let Some(bracketed) = rest.strip_prefix('[') {
+            let Some((host, after)) = bracketed.split_once(']') else { return fail("missing ']' after the IPv6 address") };
+            match after {
+                "" => (host, None),
+                _ => match after.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return fail("expected ':port' after ']'"),
+                },
+            }
+        } else if rest.matches(':').count() == 1 {
+            let (host, port) = rest.split_once(':').unwrap_or((rest, ""));
+            (host, Some(port))
+        } else {
+            // Bare IPv6 addresses have several colons and no port.
+            (rest, None)
+        };
+        if host.is_empty() {
+            return fail("host is missing");
+        }
+        let port = match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => return fail("port must be a number between 1 and 65535"),
+            },
+            None => None,
+        };
+        Ok(HostEntry { host: host.to_string(), user: user.map(String::from), port })
+    }
+
+    /// Parses `entry`, expanding a range host into one entry per address.
+    pub fn expand(entry: &str) -> Result<Vec<Self>, String> {
+        let parsed = Self::parse(entry)?;
+        if !parsed.host.contains('/') {
+            return Ok(vec![parsed]);
+        }
+        Ok(expand_cidr(&parsed.host)?
+            .into_iter()
+            .map(|host| HostEntry { host, user: parsed.user.clone(), port: parsed.port })
+            .collect())
+    }
+
+    /// Whether `entry` is an address range.
+    pub fn is_range(entry: &str) -> bool {
+        entry.contains('/')
+    }
+}
+
+/// Starting points for the feature toggles; connection settings are left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    WebServer,
+    HardenedBaseline,
+    Minimal,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::WebServer, Preset::HardenedBaseline, Preset::Minimal];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::WebServer => "Web server",
+            Preset::HardenedBaseline => "Hardened baseline",
+            Preset::Minimal => "Minimal",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Preset::WebServer => "LEMP stack with Let's Encrypt certificates, Fail2ban and swap",
+            Preset::HardenedBaseline => "Every security module except the ones that can lock you out (2FA, USB lock, IPv6 off)",
+            Preset::Minimal => "Base system and user only, no extra modules",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProvisioningConfig {
     pub ip_address: String,
+    #[serde(default = "default_ssh_port")]
+    pub ssh_port: u16,
     pub ssh_user: String,
+    /// Escalate with sudo when connecting as a non-root user.
+    #[serde(default)]
+    pub use_become: bool,
+    /// sudo password; never written to the cache.
+    #[serde(skip)]
+    pub become_password: String,
+    /// Reuse one SSH connection per host (ControlMaster); off for hosts where it misbehaves.
+    #[serde(default = "default_true")]
+    pub ssh_multiplexing: bool,
+    /// Bastion the targets are reached through (`ProxyJump`); empty connects directly.
+    #[serde(default)]
+    pub jump_host: String,
+    /// User on the jump host; empty uses the SSH default.
+    #[serde(default)]
+    pub jump_user: String,
+    /// Seconds ansible waits for an SSH connection before giving up on a host.
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u32,
+    /// Extra SSH attempts per task after a connection failure.
+    #[serde(default)]
+    pub connection_retries: u32,
+    /// Hosts ansible provisions in parallel (`--forks`), 1–100.
+    #[serde(default = "default_forks")]
+    pub forks: u32,
+    /// Python on the targets: a path, or `auto` to let ansible discover it. Empty keeps
+    /// the playbook's `/usr/bin/python3`.
+    #[serde(default = "default_python_interpreter")]
+    pub ansible_python_interpreter: String,
+    /// Air-gapped targets: the playbook skips apt cache refreshes and other downloads.
+    #[serde(default)]
+    pub offline_mode: bool,
     #[serde(default)]
     pub connection_password: String,
     pub ssh_key_path: String,
+    /// Use `inline_private_key` instead of the key file at `ssh_key_path`.
+    #[serde(default)]
+    pub use_inline_key: bool,
+    /// Pasted private key; never written to the cache.
+    #[serde(skip)]
+    pub inline_private_key: String,
     #[serde(default)]
     pub ssh_key_passphrase: String,
     pub hostname: String,
@@ -23,8 +229,18 @@ pub struct ProvisioningConfig {
     #[serde(default = "default_true")]
     pub create_user: bool,
     pub fail2ban: bool,
+    /// Fail2ban sshd jail tuning, in seconds and attempts; empty keeps the playbook's defaults.
+    #[serde(default)]
+    pub fail2ban_bantime: String,
+    #[serde(default)]
+    pub fail2ban_maxretry: String,
+    #[serde(default)]
+    pub fail2ban_findtime: String,
     pub docker: bool,
     pub swap: bool,
+    /// Swap file size in MB; 0 lets the swap role size it from RAM.
+    #[serde(default)]
+    pub swap_size_mb: u32,
     pub lemp: bool,
     pub devtools: bool,
     pub wordpress: bool,
@@ -35,14 +251,9 @@ pub struct ProvisioningConfig {
     pub file_integrity: bool,
     pub audit_logging: bool,
     pub log_monitoring: bool,
+    /// SSH 2FA with authenticator (TOTP) codes, the only method the security role sets up.
     pub advanced_protection: bool,
     #[serde(default)]
-    pub ssh_2fa_totp: bool,
-    #[serde(default)]
-    pub ssh_2fa_fido2: bool,
-    #[serde(default)]
-    pub ssh_2fa_duo: bool,
-    #[serde(default)]
     pub backups: bool,
     #[serde(default)]
     pub usb_restrictions: bool,
@@ -121,6 +332,91 @@ pub struct ProvisioningConfig {
     pub cron_jobs: bool,
     pub periodic_reboot: bool,
     pub reboot_hour: String,
+    /// Cron minute field for the periodic reboot.
+    #[serde(default = "default_reboot_minute")]
+    pub reboot_minute: String,
+    /// Playbook to run; empty means auto-detect `playbook.yml` next to the executable.
+    #[serde(default)]
+    pub playbook_path: String,
+    /// Inventory passed via `-i`; empty means use the one from ansible.cfg.
+    #[serde(default)]
+    pub inventory_path: String,
+    /// Directory ansible-playbook runs from; empty means the playbook's parent directory.
+    #[serde(default)]
+    pub working_dir: String,
+    /// `ANSIBLE_ROLES_PATH`; empty uses ansible's default.
+    #[serde(default)]
+    pub roles_path: String,
+    /// `ANSIBLE_COLLECTIONS_PATH`; empty uses ansible's default.
+    #[serde(default)]
+    pub collections_path: String,
+    /// Hand-edited version of the generated inventory; empty uses the generated one.
+    /// Kept for the session only, so a stale edit never outlives a change of hosts.
+    #[serde(skip)]
+    pub inventory_text: String,
+    /// Custom `ansible.cfg`, exported as `ANSIBLE_CONFIG`; empty uses ansible's lookup order.
+    #[serde(default)]
+    pub ansible_cfg_path: String,
+    /// `--limit` pattern for a single retry run; never written to the cache.
+    #[serde(skip)]
+    pub limit: String,
+    /// Run with `--check --diff` to preview changes; only set for a single launch.
+    #[serde(skip)]
+    pub check_mode: bool,
+    /// Password for ansible-vault encrypted vars; handed over via a temp file and never
+    /// written to the cache.
+    #[serde(skip)]
+    pub vault_password: String,
+    /// Only run tasks with these tags (`--tags`); empty runs everything.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Skip tasks with these tags (`--skip-tags`).
+    #[serde(default)]
+    pub skip_tags: Vec<String>,
+    /// Additional `-e` variables, one `key=value` per line or a single JSON object.
+    #[serde(default)]
+    pub extra_vars_raw: String,
+    /// Extra environment variables for the `ansible-playbook` child, e.g. proxy settings.
+    #[serde(default)]
+    pub env_vars: Vec<(String, String)>,
+    /// Number of output lines kept in the UI; older lines remain in the run's log file.
+    #[serde(default = "default_max_output_lines")]
+    pub max_output_lines: usize,
+    /// Keep ansible's own colors and timing lines instead of the styled, filtered view.
+    #[serde(default)]
+    pub raw_output: bool,
+    /// Group output under collapsible per-task headers.
+    #[serde(default)]
+    pub fold_tasks: bool,
+    /// Show only failure, unreachable and warning lines under their task headers.
+    #[serde(default)]
+    pub problems_only: bool,
+    /// Wrap long output lines instead of scrolling horizontally.
+    #[serde(default)]
+    pub wrap_output: bool,
+    /// Keep `profile_tasks` timing lines in the filtered view, dimmed.
+    #[serde(default)]
+    pub show_timings: bool,
+    #[serde(default)]
+    pub theme: Theme,
+    /// UI and terminal text scale, 0.8–2.0.
+    #[serde(default = "default_font_scale")]
+    pub font_scale: f32,
+    /// Tighter spacing and smaller text for small windows.
+    #[serde(default)]
+    pub compact_mode: bool,
+    /// `(regex, rgb)` pairs recoloring matching output lines.
+    #[serde(default)]
+    pub highlight_rules: Vec<(String, [u8; 3])>,
+    /// How many per-run logs to keep in `~/.rustsible/logs`.
+    #[serde(default = "default_log_retention")]
+    pub log_retention: usize,
+    /// Launch against remote targets without the confirmation dialog.
+    #[serde(default)]
+    pub skip_launch_confirm: bool,
+    /// Post a desktop notification when a run finishes while the window is unfocused.
+    #[serde(default = "default_true")]
+    pub notify_on_complete: bool,
 }
 
 impl Default for ProvisioningConfig {
@@ -131,17 +427,34 @@ impl Default for ProvisioningConfig {
 
         Self {
             ip_address: String::new(),
+            ssh_port: 22,
             ssh_user: "root".to_string(),
+            use_become: false,
+            become_password: String::new(),
+            ssh_multiplexing: true,
+            jump_host: String::new(),
+            jump_user: String::new(),
+            connect_timeout: default_connect_timeout(),
+            connection_retries: 0,
+            forks: default_forks(),
+            ansible_python_interpreter: default_python_interpreter(),
+            offline_mode: false,
             connection_password: String::new(),
             ssh_key_path: default_key_path,
+            use_inline_key: false,
+            inline_private_key: String::new(),
             ssh_key_passphrase: String::new(),
             hostname: String::new(),
             added_user: "organic".to_string(),
             user_password: String::new(),
             create_user: true,
             fail2ban: true,
+            fail2ban_bantime: String::new(),
+            fail2ban_maxretry: String::new(),
+            fail2ban_findtime: String::new(),
             docker: true,
             swap: true,
+            swap_size_mb: 0,
             lemp: false,
             devtools: true,
             wordpress: false,
@@ -153,9 +466,6 @@ impl Default for ProvisioningConfig {
             audit_logging: false,
             log_monitoring: false,
             advanced_protection: false,
-            ssh_2fa_totp: false,
-            ssh_2fa_fido2: false,
-            ssh_2fa_duo: false,
             backups: false,
             usb_restrictions: false,
             install_neovim: true,
@@ -196,32 +506,667 @@ impl Default for ProvisioningConfig {
             cron_jobs: true,
             periodic_reboot: false,
             reboot_hour: "3".to_string(),
+            reboot_minute: default_reboot_minute(),
+            playbook_path: String::new(),
+            working_dir: String::new(),
+            roles_path: String::new(),
+            collections_path: String::new(),
+            inventory_path: String::new(),
+            inventory_text: String::new(),
+            ansible_cfg_path: String::new(),
+            limit: String::new(),
+            check_mode: false,
+            vault_password: String::new(),
+            tags: Vec::new(),
+            skip_tags: Vec::new(),
+            extra_vars_raw: String::new(),
+            env_vars: Vec::new(),
+            max_output_lines: default_max_output_lines(),
+            log_retention: default_log_retention(),
+            raw_output: false,
+            fold_tasks: false,
+            problems_only: false,
+            wrap_output: false,
+            show_timings: false,
+            theme: Theme::default(),
+            font_scale: default_font_scale(),
+            compact_mode: false,
+            highlight_rules: Vec::new(),
+            skip_launch_confirm: false,
+            notify_on_complete: true,
         }
     }
 }
 
-pub fn get_cache_path() -> PathBuf {
-    let mut path = dirs::home_dir().expect("Could not find home directory");
-    path.push(".ansible_provisioning_cache.json");
-    path
+impl ProvisioningConfig {
+    /// Default config with the feature toggles of `preset`.
+    pub fn preset(preset: Preset) -> Self {
+        let mut config = Self {
+            fail2ban: false,
+            docker: false,
+            swap: false,
+            lemp: false,
+            devtools: false,
+            wordpress: false,
+            certbot: false,
+            cron_jobs: false,
+            ..Self::default()
+        };
+        match preset {
+            Preset::WebServer => {
+                config.lemp = true;
+                config.certbot = true;
+                config.fail2ban = true;
+                config.swap = true;
+                config.cron_jobs = true;
+            }
+            Preset::HardenedBaseline => {
+                config.fail2ban = true;
+                config.system_hardening = true;
+                config.apparmor = true;
+                config.rootkit_detection = true;
+                config.file_integrity = true;
+                config.audit_logging = true;
+                config.log_monitoring = true;
+                config.secure_shm = true;
+                config.lynis = true;
+                config.suricata = true;
+                config.cron_jobs = true;
+            }
+            Preset::Minimal => {}
+        }
+        config
+    }
+
+    /// Replaces the module toggles with those of `preset`, keeping connection settings,
+    /// developer tool picks and everything else.
+    pub fn apply_preset(&mut self, preset: Preset) {
+        let p = Self::preset(preset);
+        self.fail2ban = p.fail2ban;
+        self.docker = p.docker;
+        self.swap = p.swap;
+        self.lemp = p.lemp;
+        self.devtools = p.devtools;
+        self.wordpress = p.wordpress;
+        self.certbot = p.certbot;
+        self.system_hardening = p.system_hardening;
+        self.apparmor = p.apparmor;
+        self.rootkit_detection = p.rootkit_detection;
+        self.file_integrity = p.file_integrity;
+        self.audit_logging = p.audit_logging;
+        self.log_monitoring = p.log_monitoring;
+        self.advanced_protection = p.advanced_protection;
+        self.backups = p.backups;
+        self.usb_restrictions = p.usb_restrictions;
+        self.secure_shm = p.secure_shm;
+        self.lynis = p.lynis;
+        self.disable_ipv6 = p.disable_ipv6;
+        self.suricata = p.suricata;
+        self.cron_jobs = p.cron_jobs;
+        self.periodic_reboot = p.periodic_reboot;
+    }
+
+    /// Raw entries of the comma/newline-separated host field.
+    pub fn host_field_entries(&self) -> impl Iterator<Item = &str> {
+        self.ip_address.split(|c: char| c == ',' || c.is_whitespace()).filter(|h| !h.is_empty())
+    }
+
+    /// Parsed host entries with ranges expanded. Entries that don't parse are kept whole as
+    /// the host so host validation reports them.
+    pub fn host_entries(&self) -> Vec<HostEntry> {
+        self.host_field_entries()
+            .flat_map(|raw| HostEntry::expand(raw).unwrap_or_else(|_| vec![HostEntry { host: raw.to_string(), user: None, port: None }]))
+            .collect()
+    }
+
+    /// `(range, expanded host count)` for each address range in the host field.
+    pub fn host_ranges(&self) -> Vec<(&str, usize)> {
+        self.host_field_entries()
+            .filter(|raw| HostEntry::is_range(raw))
+            .map(|raw| (raw, HostEntry::expand(raw).map_or(0, |hosts| hosts.len())))
+            .collect()
+    }
+
+    /// Target hosts without their `user@` and `:port` parts, unvalidated.
+    pub fn target_hosts(&self) -> Vec<String> {
+        self.host_entries().into_iter().map(|e| e.host).collect()
+    }
+
+    /// SSH user for `host`: its `user@` override or the global user.
+    pub fn ssh_user_for(&self, host: &str) -> String {
+        self.host_entries().into_iter().find(|e| e.host == host).and_then(|e| e.user).unwrap_or_else(|| self.ssh_user.trim().to_string())
+    }
+
+    /// SSH port for `host`: its `:port` override or the global port.
+    pub fn ssh_port_for(&self, host: &str) -> u16 {
+        self.host_entries().into_iter().find(|e| e.host == host).and_then(|e| e.port).unwrap_or(self.ssh_port)
+    }
+
+    /// `ProxyJump` destination, `user@host` or just `host`, when a jump host is set.
+    pub fn proxy_jump(&self) -> Option<String> {
+        let host = self.jump_host.trim();
+        if host.is_empty() {
+            return None;
+        }
+        let user = self.jump_user.trim();
+        Some(if user.is_empty() { host.to_string() } else { format!("{}@{}", user, host) })
+    }
+
+    /// Non-empty fail2ban jail settings as `(extra var, value)` pairs.
+    pub fn fail2ban_settings(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("fail2ban_bantime", self.fail2ban_bantime.trim()),
+            ("fail2ban_maxretry", self.fail2ban_maxretry.trim()),
+            ("fail2ban_findtime", self.fail2ban_findtime.trim()),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+
+    /// Enabled options that can reboot the server or lock users out, for the launch confirmation.
+    pub fn destructive_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if self.periodic_reboot { options.push("Periodic reboots".to_string()); }
+        if self.system_hardening { options.push("Kernel hardening".to_string()); }
+        if self.usb_restrictions { options.push("USB restrictions".to_string()); }
+        if self.disable_ipv6 { options.push("Disable IPv6".to_string()); }
+        if self.advanced_protection { options.push("SSH two-factor authentication".to_string()); }
+        options
+    }
+
+    /// The edited inventory passed with `-i`, unless an inventory file is configured.
+    pub fn edited_inventory(&self) -> Option<&str> {
+        Some(self.inventory_text.as_str()).filter(|text| self.inventory_path.trim().is_empty() && !text.trim().is_empty())
+    }
+
+    /// The `(hour, minute)` cron fields the reboot job is installed with.
+    ///
+    /// Plain numbers and `*/N` steps — the forms the Maintenance tab edits — are clamped
+    /// into range; other valid cron fields are kept as-is and anything invalid falls back
+    /// to the default, so the UI and the emitted extra vars always agree.
+    pub fn reboot_schedule(&self) -> (String, String) {
+        let (hour, minute) = (self.reboot_hour.trim(), self.reboot_minute.trim());
+        let hour = if let Ok(h) = hour.parse::<u32>() {
+            h.min(23).to_string()
+        } else if let Some(n) = hour.strip_prefix("*/").and_then(|n| n.parse::<u32>().ok()) {
+            format!("*/{}", n.clamp(1, 23))
+        } else if validate_cron_field(hour, 0, 23).is_ok() {
+            hour.to_string()
+        } else {
+            ProvisioningConfig::default().reboot_hour
+        };
+        let minute = if let Ok(m) = minute.parse::<u32>() {
+            m.min(59).to_string()
+        } else if validate_cron_field(minute, 0, 59).is_ok() {
+            minute.to_string()
+        } else {
+            default_reboot_minute()
+        };
+        (hour, minute)
+    }
+
+    /// Rewrites the stored reboot fields to `reboot_schedule()`, returning the names of
+    /// the fields that changed beyond whitespace.
+    pub fn normalize_reboot_schedule(&mut self) -> Vec<String> {
+        let (hour, minute) = self.reboot_schedule();
+        let mut changed = Vec::new();
+        if hour != self.reboot_hour.trim() { changed.push("reboot_hour".to_string()); }
+        if minute != self.reboot_minute.trim() { changed.push("reboot_minute".to_string()); }
+        self.reboot_hour = hour;
+        self.reboot_minute = minute;
+        changed
+    }
+
+    /// Become is only needed when the SSH user isn't already root.
+    pub fn needs_become(&self) -> bool {
+        self.use_become && self.ssh_user.trim() != "root"
+    }
+
+    /// `(ansible variable, enabled)` for every individual developer tool toggle.
+    /// The variable names match the `install_*` flags used by the dev_tools role.
+    pub fn devtool_flags(&self) -> [(&'static str, bool); 31] {
+        [
+            ("install_neovim", self.install_neovim),
+            ("install_nodejs", self.install_nodejs),
+            ("install_claude_code", self.install_claude_code),
+            ("install_gemini", self.install_gemini),
+            ("install_kiro", self.install_kiro),
+            ("install_github_cli", self.install_github_cli),
+            ("install_btop", self.install_btop),
+            ("install_tldr", self.install_tldr),
+            ("install_lazygit", self.install_lazygit),
+            ("install_tmux", self.install_tmux),
+            ("install_zsh", self.install_zsh),
+            ("install_ripgrep", self.install_ripgrep),
+            ("install_fd", self.install_fd),
+            ("install_duf", self.install_duf),
+            ("install_ncdu", self.install_ncdu),
+            ("install_lnav", self.install_lnav),
+            ("install_uv", self.install_uv),
+            ("install_fzf", self.install_fzf),
+            ("install_bat", self.install_bat),
+            ("install_eza", self.install_eza),
+            ("install_zoxide", self.install_zoxide),
+            ("install_jq", self.install_jq),
+            ("install_htop", self.install_htop),
+            ("install_gping", self.install_gping),
+            ("install_nmap", self.install_nmap),
+            ("install_autossh", self.install_autossh),
+            ("install_starship", self.install_starship),
+            ("install_direnv", self.install_direnv),
+            ("install_fish", self.install_fish),
+            ("install_micro", self.install_micro),
+            ("install_ranger", self.install_ranger),
+        ]
+    }
+
+    /// Secret values that must never appear in logs or copied output.
+    pub fn secrets(&self) -> Vec<&str> {
+        [
+            self.connection_password.as_str(),
+            self.user_password.as_str(),
+            self.ssh_key_passphrase.as_str(),
+            self.become_password.as_str(),
+            self.vault_password.as_str(),
+        ]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect()
+    }
+}
+
+/// Expands a leading `~` or `~/` to the home directory. Absolute and relative paths, and
+/// `~user` forms, are returned unchanged.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    let path = path.trim();
+    let rest = if path == "~" { Some("") } else { path.strip_prefix("~/") };
+    match rest.zip(dirs::home_dir()) {
+        Some(("", home)) => home,
+        Some((rest, home)) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Overrides the settings cache location with a file path.
+pub const CONFIG_PATH_ENV: &str = "RUSTSIBLE_CONFIG";
+
+/// Settings cache: `$RUSTSIBLE_CONFIG` when set, otherwise `rustsible/settings.json` in the
+/// user config dir (`$XDG_CONFIG_HOME`, or `~/.config`, on Linux).
+pub fn get_cache_path() -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var(CONFIG_PATH_ENV).ok().filter(|p| !p.trim().is_empty()) {
+        return Ok(expand_tilde(&path));
+    }
+    let dir = dirs::config_dir().ok_or("Could not determine the config directory; settings won't be saved")?;
+    Ok(dir.join("rustsible").join("settings.json"))
+}
+
+/// Cache location used before settings moved to the config dir.
+fn legacy_cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ansible_provisioning_cache.json"))
+}
+
+/// Moves a cache from the legacy home-directory location to `cache_path` the first time
+/// the new location is used. If the move fails the legacy file is read in place.
+fn migrate_legacy_cache(cache_path: &Path) -> Option<PathBuf> {
+    let legacy = legacy_cache_path().filter(|p| p.exists())?;
+    if cache_path.exists() || std::env::var_os(CONFIG_PATH_ENV).is_some() {
+        return None;
+    }
+    let moved = cache_path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::copy(&legacy, cache_path));
+    match moved {
+        Ok(_) => {
+            log::info!("moved settings from {} to {}", legacy.display(), cache_path.display());
+            let _ = fs::remove_file(&legacy);
+            let _ = fs::remove_file(cache_sibling(&legacy, ".bak"));
+            None
+        }
+        Err(e) => {
+            log::warn!("couldn't move settings from {}: {}", legacy.display(), e);
+            Some(legacy)
+        }
+    }
+}
+
+/// Where settings are saved when the normal cache location can't be written, e.g. on a
+/// read-only home directory: the per-user runtime dir, or else a uid-named directory in
+/// the shared temp dir. Either way the directory must pass `is_private`.
+pub fn fallback_cache_path() -> PathBuf {
+    let dir = match dirs::runtime_dir() {
+        Some(runtime) => runtime.join("rustsible"),
+        #[cfg(unix)]
+        None => std::env::temp_dir().join(format!("rustsible-{}", unsafe { libc::getuid() })),
+        #[cfg(not(unix))]
+        None => std::env::temp_dir().join("rustsible"),
+    };
+    dir.join("settings.json")
+}
+
+/// True if `path` itself (links aren't followed) belongs to the current user and isn't
+/// writable by anyone else, so another local user can't have planted or edited it.
+fn is_private(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        fs::symlink_metadata(path).is_ok_and(|m| m.uid() == unsafe { libc::getuid() } && m.mode() & 0o022 == 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.exists()
+    }
+}
+
+/// The fallback cache if it and its directory are private to the current user.
+fn trusted_fallback_cache() -> Option<PathBuf> {
+    let path = fallback_cache_path();
+    let private = path.parent().is_some_and(is_private) && is_private(&path);
+    if !private && path.exists() {
+        log::warn!("ignoring {}: not owned by this user or writable by others", path.display());
+    }
+    private.then_some(path)
+}
+
+/// Writes the fallback cache, creating its directory owner-only (0700) first.
+fn write_fallback_cache(path: &Path, config: &ProvisioningConfig) -> Result<(), String> {
+    let dir = path.parent().ok_or_else(|| format!("Invalid fallback cache path: {}", path.display()))?;
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    match builder.create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+    }
+    if !is_private(dir) {
+        return Err(format!("Refusing to use {}: not owned by this user or writable by others", dir.display()));
+    }
+    write_cache(path, config)
+}
+
+/// Sibling of the cache file with `suffix` appended, e.g. `.bak` or `.tmp`.
+fn cache_sibling(cache_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = cache_path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Deserializes a cached config, keeping every field that still parses.
+///
+/// Fields whose stored value no longer fits the current schema are reset to their
+/// defaults and returned by name, instead of discarding the whole cache.
+fn parse_config_lenient(contents: &str) -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let (mut config, mut reset) = parse_fields_lenient(contents)?;
+    reset.extend(config.normalize_reboot_schedule());
+    Ok((config, reset))
+}
+
+fn parse_fields_lenient(contents: &str) -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let cached: serde_json::Value = serde_json::from_str(contents)
+        .map_err(|e| format!("Failed to parse cache: {}", e))?;
+    if let Ok(config) = serde_json::from_value(cached.clone()) {
+        return Ok((config, Vec::new()));
+    }
+    let serde_json::Value::Object(cached) = cached else {
+        return Err("Failed to parse cache: expected a JSON object".to_string());
+    };
+
+    let mut merged = serde_json::to_value(ProvisioningConfig::default())
+        .map_err(|e| format!("Failed to serialize defaults: {}", e))?;
+    let mut reset = Vec::new();
+    for (key, value) in cached {
+        let Some(fields) = merged.as_object_mut() else { break };
+        if !fields.contains_key(&key) {
+            continue; // Removed setting; serde ignores unknown keys anyway.
+        }
+        let previous = fields.insert(key.clone(), value);
+        if serde_json::from_value::<ProvisioningConfig>(merged.clone()).is_err() {
+            if let (Some(fields), Some(previous)) = (merged.as_object_mut(), previous) {
+                fields.insert(key.clone(), previous);
+            }
+            reset.push(key);
+        }
+    }
+    let config = serde_json::from_value(merged).map_err(|e| format!("Failed to parse cache: {}", e))?;
+    Ok((config, reset))
 }
 
-pub fn load_cache() -> Result<ProvisioningConfig, String> {
-    let cache_path = get_cache_path();
-    if cache_path.exists() {
-        let contents = fs::read_to_string(&cache_path)
-            .map_err(|e| format!("Failed to read cache: {}", e))?;
-        serde_json::from_str(&contents)
-            .map_err(|e| format!("Failed to parse cache: {}", e))
-    } else {
-        Ok(ProvisioningConfig::default())
+/// Older caches stored per-method 2FA picks (`ssh_2fa_totp`/`_fido2`/`_duo`) without the
+/// Advanced Protection umbrella; any of them means 2FA was wanted.
+fn legacy_2fa_enabled(contents: &str) -> bool {
+    let Ok(serde_json::Value::Object(cached)) = serde_json::from_str(contents) else { return false };
+    ["ssh_2fa_totp", "ssh_2fa_fido2", "ssh_2fa_duo"].iter().any(|key| cached.get(*key) == Some(&serde_json::Value::Bool(true)))
+}
+
+fn read_config(path: &Path) -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read cache: {}", e))?;
+    let (mut config, reset) = parse_config_lenient(&contents)?;
+    if legacy_2fa_enabled(&contents) {
+        config.advanced_protection = true;
+    }
+    Ok((config, reset))
+}
+
+/// Loads a config saved elsewhere, e.g. a copy of the cache or a YAML export passed to
+/// `--headless`.
+pub fn load_config_file(path: &Path) -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let is_yaml = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml"));
+    if !is_yaml {
+        return read_config(path);
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    config_from_yaml(&text, &ProvisioningConfig::default())
+}
+
+/// Loads the cached config, falling back to the `.bak` copy if the primary is unreadable.
+/// A fallback cache written after the normal one holds the newer settings and wins.
+/// Also returns the names of fields that were reset because their cached value was invalid.
+pub fn load_cache() -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let fallback = trusted_fallback_cache();
+    let cache_path = match (get_cache_path(), fallback) {
+        (Ok(path), fallback) => {
+            // A failed migration leaves the settings at the legacy path; read them there.
+            let primary = migrate_legacy_cache(&path).unwrap_or(path);
+            match fallback {
+                Some(fallback) if modified(&fallback) > modified(&primary) => fallback,
+                _ => primary,
+            }
+        }
+        (Err(_), Some(fallback)) => fallback,
+        (Err(e), None) => return Err(e),
+    };
+    if !cache_path.exists() {
+        return Ok((ProvisioningConfig::default(), Vec::new()));
+    }
+    read_config(&cache_path).or_else(|primary_err| {
+        read_config(&cache_sibling(&cache_path, ".bak")).map_err(|_| primary_err)
+    })
+}
+
+/// Saves the config to the normal cache location, or to `fallback_cache_path` when that
+/// can't be written. Returns the fallback path when it was used.
+pub fn save_cache(config: &ProvisioningConfig) -> Result<Option<PathBuf>, String> {
+    let fallback = fallback_cache_path();
+    match get_cache_path().and_then(|path| write_cache(&path, config)) {
+        Ok(()) => {
+            let _ = fs::remove_file(&fallback);
+            Ok(None)
+        }
+        Err(e) => write_fallback_cache(&fallback, config).map(|_| Some(fallback)).map_err(|_| e),
     }
 }
 
-pub fn save_cache(config: &ProvisioningConfig) -> Result<(), String> {
-    let cache_path = get_cache_path();
+/// Writes the config atomically: the JSON goes to a temp file that is renamed over the
+/// cache, and the previous good cache is kept as `.bak`.
+fn write_cache(cache_path: &Path, config: &ProvisioningConfig) -> Result<(), String> {
     let json = serde_json::to_string_pretty(config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
-    fs::write(&cache_path, json)
-        .map_err(|e| format!("Failed to write cache: {}", e))
+
+    if let Some(dir) = cache_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let tmp_path = cache_sibling(cache_path, ".tmp");
+    // A fresh owner-only file, so a link planted in a shared temp dir isn't followed.
+    let _ = fs::remove_file(&tmp_path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp_path)
+        .map_err(|e| format!("Failed to write cache: {}", e))?;
+    file.write_all(json.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write cache: {}", e))?;
+    drop(file);
+
+    if read_config(cache_path).is_ok() {
+        let _ = fs::copy(cache_path, cache_sibling(cache_path, ".bak"));
+    }
+    fs::rename(&tmp_path, cache_path)
+        .map_err(|e| format!("Failed to replace cache: {}", e))
+}
+
+/// Password fields, left out of YAML exports unless explicitly included.
+const SECRET_FIELDS: [&str; 3] = ["connection_password", "user_password", "ssh_key_passphrase"];
+
+/// Renders the config as diff-friendly YAML: one top-level key per field, sorted, with
+/// lists in block style.
+pub fn config_to_yaml(config: &ProvisioningConfig, include_secrets: bool) -> Result<String, String> {
+    let value = serde_json::to_value(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let serde_json::Value::Object(mut fields) = value else {
+        return Err("Failed to serialize config: expected an object".to_string());
+    };
+    let mut yaml = String::from("# Rustsible GUI config\n");
+    if !include_secrets {
+        yaml.push_str("# Passwords are not included.\n");
+        fields.retain(|key, _| !SECRET_FIELDS.contains(&key.as_str()));
+    }
+    yaml.push_str(&serde_yaml::to_string(&fields).map_err(|e| format!("Failed to serialize config: {}", e))?);
+    Ok(yaml)
+}
+
+/// Reads a config written by `config_to_yaml` or edited by hand. Passwords missing from
+/// the file keep their values from `current`; other missing fields get their defaults, and
+/// fields with unusable values are reset and returned by name.
+pub fn config_from_yaml(text: &str, current: &ProvisioningConfig) -> Result<(ProvisioningConfig, Vec<String>), String> {
+    let mut fields = match serde_yaml::from_str(text).map_err(|e| format!("Failed to parse YAML: {}", e))? {
+        serde_json::Value::Object(fields) => fields,
+        serde_json::Value::Null => serde_json::Map::new(),
+        _ => return Err("Failed to parse YAML: expected a mapping of settings".to_string()),
+    };
+
+    if let serde_json::Value::Object(current) = serde_json::to_value(current).map_err(|e| format!("Failed to serialize config: {}", e))? {
+        for key in SECRET_FIELDS {
+            if let (false, Some(value)) = (fields.contains_key(key), current.get(key)) {
+                fields.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    parse_config_lenient(&serde_json::Value::Object(fields).to_string())
+}
+
+/// Writes a YAML export to `path`.
+pub fn save_config_yaml(path: &Path, config: &ProvisioningConfig, include_secrets: bool) -> Result<(), String> {
+    let yaml = config_to_yaml(config, include_secrets)?;
+    fs::write(path, yaml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> PathBuf {
+        dirs::home_dir().expect("home directory")
+    }
+
+    #[test]
+    fn expand_tilde_alone() {
+        assert_eq!(expand_tilde("~"), home());
+        assert_eq!(expand_tilde("  ~  "), home());
+    }
+
+    #[test]
+    fn expand_tilde_with_path() {
+        assert_eq!(expand_tilde("~/x"), home().join("x"));
+        assert_eq!(expand_tilde("~/.ssh/id_ed25519"), home().join(".ssh/id_ed25519"));
+    }
+
+    #[test]
+    fn expand_tilde_leaves_other_users_alone() {
+        assert_eq!(expand_tilde("~user/.ssh/id_rsa"), PathBuf::from("~user/.ssh/id_rsa"));
+    }
+
+    #[test]
+    fn expand_tilde_without_tilde() {
+        assert_eq!(expand_tilde("/etc/ansible/ansible.cfg"), PathBuf::from("/etc/ansible/ansible.cfg"));
+        assert_eq!(expand_tilde("playbooks/site.yml"), PathBuf::from("playbooks/site.yml"));
+        assert_eq!(expand_tilde("keys/~backup"), PathBuf::from("keys/~backup"));
+    }
+
+    #[test]
+    fn yaml_export_round_trips_without_secrets() {
+        let config = ProvisioningConfig {
+            hostname: "web: 01 # not a comment".to_string(),
+            tags: vec!["base".to_string(), "'quoted'".to_string()],
+            extra_vars_raw: "motd=hello\nregion=eu-west-1".to_string(),
+            forks: 12,
+            connection_password: "hunter2".to_string(),
+            ..ProvisioningConfig::default()
+        };
+        let yaml = config_to_yaml(&config, false).unwrap();
+        assert!(!yaml.contains("hunter2") && !yaml.contains("connection_password"), "{}", yaml);
+
+        let current = ProvisioningConfig { connection_password: "kept".to_string(), ..ProvisioningConfig::default() };
+        let (loaded, reset) = config_from_yaml(&yaml, &current).unwrap();
+        assert!(reset.is_empty());
+        assert_eq!(loaded.hostname, config.hostname);
+        assert_eq!(loaded.tags, config.tags);
+        assert_eq!(loaded.extra_vars_raw, config.extra_vars_raw);
+        assert_eq!(loaded.forks, 12);
+        assert_eq!(loaded.connection_password, "kept");
+
+        let (with_secrets, _) = config_from_yaml(&config_to_yaml(&config, true).unwrap(), &current).unwrap();
+        assert_eq!(with_secrets.connection_password, "hunter2");
+    }
+
+    #[test]
+    fn yaml_import_resets_only_unusable_fields() {
+        let yaml = "hostname: box\nforks: lots\ntags:\n  - web\nremoved_setting: 1\n";
+        let (loaded, reset) = config_from_yaml(yaml, &ProvisioningConfig::default()).unwrap();
+        assert_eq!(reset, ["forks"]);
+        assert_eq!(loaded.forks, ProvisioningConfig::default().forks);
+        assert_eq!(loaded.hostname, "box");
+        assert_eq!(loaded.tags, ["web"]);
+
+        assert!(config_from_yaml("", &ProvisioningConfig::default()).is_ok());
+        assert!(config_from_yaml("- just\n- a list\n", &ProvisioningConfig::default()).is_err());
+        assert!(config_from_yaml("hostname: [unclosed\n", &ProvisioningConfig::default()).is_err());
+    }
+
+    #[test]
+    fn run_only_secrets_stay_out_of_the_cache() {
+        let config = ProvisioningConfig {
+            become_password: "sudo-secret".to_string(),
+            vault_password: "vault-secret".to_string(),
+            inline_private_key: "key".to_string(),
+            ..Default::default()
+        };
+        let cached = serde_json::to_string(&config).unwrap();
+        assert!(!cached.contains("sudo-secret") && !cached.contains("vault-secret"));
+        assert!(!config_to_yaml(&config, true).unwrap().contains("vault-secret"));
+    }
+
+    #[test]
+    fn legacy_2fa_methods_turn_on_advanced_protection() {
+        assert!(legacy_2fa_enabled(r#"{"advanced_protection": false, "ssh_2fa_duo": true}"#));
+        assert!(!legacy_2fa_enabled(r#"{"advanced_protection": false, "ssh_2fa_totp": false}"#));
+        assert!(!legacy_2fa_enabled("not json"));
+    }
 }
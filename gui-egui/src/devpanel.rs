@@ -0,0 +1,104 @@
+//! Developer view that runs a sample ansible transcript through the output pipeline so
+//! coloring and filtering can be checked by eye. Only shown when `RUSTSIBLE_DEV` is set.
+
+use crate::failure::first_failed_task;
+use crate::timings::{parse_task_timings, TaskTiming};
+use crate::{display_stdout_line, is_timing_only_line, strip_ansi};
+use std::path::Path;
+
+/// Set to `1` to check the bundled sample, or to a transcript path to check that instead.
+const ENV_VAR: &str = "RUSTSIBLE_DEV";
+
+const SAMPLE: &str = include_str!("../fixtures/sample_transcript.txt");
+/// Lines the output view should keep from the bundled sample. Update it together with the
+/// fixture or the filtering rules.
+const SAMPLE_SHOWN_LINES: usize = 30;
+/// Slowest task in the bundled sample according to its timing lines.
+const SAMPLE_SLOWEST_TASK: &str = "common : Install base packages";
+
+pub struct Transcript {
+    pub source: String,
+    pub raw: Vec<String>,
+    pub shown: Vec<String>,
+    pub timings: Vec<TaskTiming>,
+    expected: Option<(usize, &'static str)>,
+}
+
+impl Transcript {
+    /// Loads the transcript selected by `RUSTSIBLE_DEV`, or `None` when it isn't set.
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let value = std::env::var(ENV_VAR).ok().filter(|v| !v.trim().is_empty())?;
+        if value.trim() == "1" {
+            return Some(Ok(Self::new("bundled sample".to_string(), SAMPLE, Some((SAMPLE_SHOWN_LINES, SAMPLE_SLOWEST_TASK)))));
+        }
+        let path = Path::new(value.trim());
+        Some(
+            std::fs::read_to_string(path)
+                .map(|text| Self::new(path.display().to_string(), &text, None))
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        )
+    }
+
+    fn new(source: String, text: &str, expected: Option<(usize, &'static str)>) -> Self {
+        let raw: Vec<String> = text.lines().map(String::from).collect();
+        let shown = raw.iter().filter_map(|line| display_stdout_line(line, false, false)).collect();
+        let timings = parse_task_timings(raw.iter().map(|line| strip_ansi(line)).collect::<Vec<_>>().iter().map(String::as_str));
+        Self { source, raw, shown, timings, expected }
+    }
+
+    /// Everything the filtering got wrong; empty when the pipeline behaves.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some((lines, slowest)) = self.expected {
+            if lines != self.shown.len() {
+                problems.push(format!("Expected {} visible lines, got {}", lines, self.shown.len()));
+            }
+            if self.timings.first().map(|t| t.name.as_str()) != Some(slowest) {
+                problems.push(format!("Expected '{}' to be the slowest task", slowest));
+            }
+            // The sample's only failure is followed by `...ignoring`.
+            let plain: Vec<String> = self.raw.iter().map(|line| strip_ansi(line)).collect();
+            if let Some(task) = first_failed_task(&plain) {
+                problems.push(format!("Ignored failure reported as the failing task: {}", task.task));
+            }
+        }
+        for line in &self.shown {
+            if is_timing_only_line(line) {
+                problems.push(format!("Timing line survived: {}", line));
+            } else if strip_ansi(line) != *line {
+                problems.push(format!("Escape sequence survived: {:?}", line));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Transcript {
+        Transcript::new("bundled sample".to_string(), SAMPLE, Some((SAMPLE_SHOWN_LINES, SAMPLE_SLOWEST_TASK)))
+    }
+
+    #[test]
+    fn sample_keeps_a_stable_line_count() {
+        let transcript = sample();
+        assert_eq!(transcript.raw.len(), 49);
+        assert_eq!(transcript.shown.len(), SAMPLE_SHOWN_LINES);
+    }
+
+    #[test]
+    fn sample_drops_every_timing_line() {
+        let transcript = sample();
+        let timing_lines = transcript.raw.iter().filter(|line| is_timing_only_line(&strip_ansi(line))).count();
+        // Eight profile_tasks lines, including the French and German ones.
+        assert_eq!(timing_lines, 8);
+        assert!(transcript.shown.iter().all(|line| !is_timing_only_line(line)), "{:#?}", transcript.shown);
+    }
+
+    #[test]
+    fn sample_passes_the_self_test() {
+        assert_eq!(sample().problems(), Vec::<String>::new());
+    }
+}
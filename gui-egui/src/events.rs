@@ -0,0 +1,21 @@
+use crate::recap::RecapSummary;
+use serde::Serialize;
+use std::io::Write;
+
+/// A provisioning event, written to stdout as one JSON line when `--json-events` is set.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event<'a> {
+    Start { hosts: &'a [String] },
+    Task { name: &'a str, index: usize },
+    Error { message: &'a str },
+    Recap(&'a RecapSummary),
+    Complete { success: bool, failure: Option<&'a str> },
+}
+
+/// Writes `event` as a line of newline-delimited JSON and flushes so readers see it at once.
+pub fn emit(event: &Event) {
+    let Ok(json) = serde_json::to_string(event) else { return };
+    let mut out = std::io::stdout().lock();
+    let _ = writeln!(out, "{}", json).and_then(|_| out.flush());
+}
@@ -0,0 +1,282 @@
+use crate::command::{ansible_env, build_ansible_args, user_env_vars, BECOME_PASS_ENV, CONN_PASS_ENV, CONTROL_PATH_DIR_ENV, USER_PASS_ENV};
+use crate::config::ProvisioningConfig;
+use crate::style::{ansible_line_style, palette};
+use eframe::egui::Color32;
+use std::path::Path;
+
+/// Secret fields swapped for environment variables in exported scripts, plus the temp
+/// file holding an edited inventory.
+const SECRET_VARS: [&str; 3] = ["SSH_KEY_FILE", "VAULT_PASSWORD_FILE", "INVENTORY_FILE"];
+
+/// Name fragments that mark a user environment variable as a credential.
+const SECRET_NAME_HINTS: [&str; 4] = ["TOKEN", "PASS", "SECRET", "KEY"];
+
+/// Whether a user environment variable's name looks like a credential. Scripts require
+/// these from the environment and the preview masks them.
+fn looks_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SECRET_NAME_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+/// Stand-in value that survives `build_ansible_args` and is rewritten into `$VAR` after quoting.
+fn placeholder(var: &str) -> String {
+    format!("@@{}@@", var)
+}
+
+/// Single-quotes `arg` for POSIX shells, re-opening the quotes around any secret placeholder
+/// so the variable is expanded at run time.
+fn shell_quote(arg: &str) -> String {
+    let mut quoted = format!("'{}'", arg.replace('\'', r"'\''"));
+    for var in SECRET_VARS {
+        quoted = quoted.replace(&placeholder(var), &format!("'\"${{{}}}\"'", var));
+    }
+    quoted
+}
+
+/// Renders a standalone bash script reproducing the GUI's `ansible-playbook` run.
+///
+/// Passwords and credential-looking user variables are never written to the file; the
+/// script requires them as environment variables instead.
+pub fn render_shell_script(config: &ProvisioningConfig, working_dir: Option<&Path>) -> String {
+    let mut scrubbed = config.clone();
+    let user_vars = user_env_vars(config);
+    let mut required = Vec::new();
+    if !config.connection_password.is_empty() {
+        required.push(CONN_PASS_ENV);
+    }
+    if config.use_inline_key {
+        // The pasted key only exists in memory; the script takes a key file instead.
+        scrubbed.ssh_key_path = placeholder("SSH_KEY_FILE");
+        required.push("SSH_KEY_FILE");
+    }
+    if !config.vault_password.is_empty() {
+        required.push("VAULT_PASSWORD_FILE");
+    }
+    if config.create_user && !config.user_password.is_empty() {
+        required.push(USER_PASS_ENV);
+    }
+    if config.needs_become() && !config.become_password.is_empty() {
+        required.push(BECOME_PASS_ENV);
+    }
+    required.extend(user_vars.iter().map(|(key, _)| key.as_str()).filter(|key| looks_secret(key)));
+
+    let mut script = String::from("#!/usr/bin/env bash\n");
+    script.push_str("# Generated by Rustsible GUI. Secrets are read from the environment.\n");
+    script.push_str("set -euo pipefail\n\n");
+    for var in &required {
+        script.push_str(&format!(": \"${{{var}:?set {var} before running}}\"\n"));
+    }
+    if !config.ssh_key_passphrase.is_empty() {
+        script.push_str("# The SSH key is passphrase-protected: load it with ssh-add before running.\n");
+    }
+    if !required.is_empty() || !config.ssh_key_passphrase.is_empty() {
+        script.push('\n');
+    }
+    if let Some(dir) = working_dir {
+        script.push_str(&format!("cd {}\n", shell_quote(&dir.display().to_string())));
+    }
+    // The same environment as a GUI run, minus the secrets required above and the GUI's
+    // own control socket directory (ansible falls back to ~/.ansible/cp).
+    for (key, value) in ansible_env(config, Path::new("")) {
+        if !is_masked_env(config, &key) && key != CONTROL_PATH_DIR_ENV {
+            script.push_str(&format!("export {}={}\n", key, shell_quote(&value)));
+        }
+    }
+    if let Some(inventory) = config.edited_inventory() {
+        script.push_str("INVENTORY_FILE=\"$(mktemp)\"\n");
+        script.push_str("trap 'rm -f \"$INVENTORY_FILE\"' EXIT\n");
+        script.push_str("cat > \"$INVENTORY_FILE\" <<'INVENTORY'\n");
+        script.push_str(inventory.trim_end());
+        script.push_str("\nINVENTORY\n");
+    }
+    script.push('\n');
+
+    let mut args = build_ansible_args(&scrubbed);
+    if !config.vault_password.is_empty() {
+        args.push("--vault-password-file".to_string());
+        args.push(placeholder("VAULT_PASSWORD_FILE"));
+    }
+    if config.edited_inventory().is_some() {
+        args.push("-i".to_string());
+        args.push(placeholder("INVENTORY_FILE"));
+    }
+
+    // No `exec`, so the EXIT trap still removes the temp inventory.
+    script.push_str("ansible-playbook");
+    for arg in args {
+        script.push_str(" \\\n  ");
+        script.push_str(&shell_quote(&arg));
+    }
+    script.push('\n');
+    script
+}
+
+/// Mask shown in place of secret values in the command preview.
+const MASK: &str = "***";
+
+/// Whether the preview masks `key`: the password variables, and user environment
+/// variables whose names look like credentials.
+fn is_masked_env(config: &ProvisioningConfig, key: &str) -> bool {
+    let user_var = config.env_vars.iter().any(|(k, _)| k.trim() == key);
+    [CONN_PASS_ENV, BECOME_PASS_ENV, USER_PASS_ENV].contains(&key) || (user_var && looks_secret(key))
+}
+
+/// Renders the exact environment and `ansible-playbook` invocation a launch would use,
+/// with passwords masked and temp files shown as placeholders.
+pub fn render_command_preview(config: &ProvisioningConfig, control_path_dir: &Path) -> String {
+    let mut scrubbed = config.clone();
+    if config.use_inline_key {
+        scrubbed.ssh_key_path = "<temp key file>".to_string();
+    }
+
+    let mut preview = String::new();
+    for (key, value) in ansible_env(config, control_path_dir) {
+        let value = if is_masked_env(config, &key) { MASK.to_string() } else { shell_quote(&value) };
+        preview.push_str(&format!("{}={} \\\n", key, value));
+    }
+    let mut args = build_ansible_args(&scrubbed);
+    if !config.vault_password.is_empty() {
+        args.push("--vault-password-file".to_string());
+        args.push("<temp file>".to_string());
+    }
+    if config.edited_inventory().is_some() {
+        args.push("-i".to_string());
+        args.push("<temp inventory file>".to_string());
+    }
+    preview.push_str("ansible-playbook");
+    for arg in args {
+        preview.push_str(" \\\n  ");
+        preview.push_str(&shell_quote(&arg));
+    }
+    preview
+}
+
+/// Writes the script to `path` and marks it executable.
+pub fn write_shell_script(path: &Path, contents: &str) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to mark {} executable: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn css_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Renders log lines as a standalone HTML page colored like the output panel.
+pub fn render_html_log(lines: &[String], title: &str) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n</head>\n", escape_html(title)));
+    html.push_str(&format!(
+        "<body style=\"margin:0;background:{};\">\n<pre style=\"margin:0;padding:24px;font:13px/1.4 ui-monospace,Menlo,Consolas,monospace;white-space:pre-wrap;\">\n",
+        css_color(palette().terminal_bg)
+    ));
+    for line in lines {
+        let (color, bold) = ansible_line_style(line);
+        let weight = if bold { "font-weight:bold;" } else { "" };
+        html.push_str(&format!("<span style=\"color:{};{}\">{}</span>\n", css_color(color), weight, escape_html(line)));
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// Writes the log to `path`, as colored HTML when the extension is `.html`/`.htm` and as
+/// plain text otherwise.
+pub fn save_log(path: &Path, lines: &[String]) -> Result<(), String> {
+    let is_html = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+    let contents = if is_html {
+        let title = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "Provisioning log".to_string());
+        render_html_log(lines, &title)
+    } else {
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    };
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_removes_the_temp_inventory_on_exit() {
+        let config = ProvisioningConfig {
+            ip_address: "203.0.113.10".to_string(),
+            inventory_text: "[web]\n203.0.113.10\n".to_string(),
+            ..ProvisioningConfig::default()
+        };
+        let script = render_shell_script(&config, None);
+        let trap = script.find("trap 'rm -f \"$INVENTORY_FILE\"' EXIT\n").expect("trap");
+        assert!(script.find("INVENTORY_FILE=\"$(mktemp)\"").is_some_and(|mktemp| mktemp < trap), "{}", script);
+        assert!(script.contains("\nansible-playbook \\\n") && !script.contains("exec "), "{}", script);
+        assert!(script.contains("'-i' \\\n  ''\"${INVENTORY_FILE}\"''"), "{}", script);
+    }
+
+    #[test]
+    fn preview_masks_secret_looking_env_vars() {
+        let config = ProvisioningConfig {
+            ip_address: "203.0.113.10".to_string(),
+            connection_password: "hunter2".to_string(),
+            env_vars: vec![
+                ("GITHUB_TOKEN".to_string(), "ghp_abc".to_string()),
+                ("aws_secret_access_key".to_string(), "wJalr".to_string()),
+                ("DB_PASSWORD".to_string(), "pg-pass".to_string()),
+                ("HTTP_PROXY".to_string(), "http://proxy:3128".to_string()),
+            ],
+            ..ProvisioningConfig::default()
+        };
+        let preview = render_command_preview(&config, Path::new("/tmp/cp"));
+        for secret in ["hunter2", "ghp_abc", "wJalr", "pg-pass"] {
+            assert!(!preview.contains(secret), "{} in {}", secret, preview);
+        }
+        assert!(preview.contains("GITHUB_TOKEN=*** \\\n"), "{}", preview);
+        assert!(preview.contains("HTTP_PROXY='http://proxy:3128' \\\n"), "{}", preview);
+        assert!(preview.contains("ANSIBLE_SSH_RETRIES='"), "{}", preview);
+    }
+
+    #[test]
+    fn script_requires_secret_looking_env_vars() {
+        let config = ProvisioningConfig {
+            ip_address: "203.0.113.10".to_string(),
+            env_vars: vec![
+                ("GITHUB_TOKEN".to_string(), "ghp_abc".to_string()),
+                ("HTTP_PROXY".to_string(), "http://proxy:3128".to_string()),
+            ],
+            ..ProvisioningConfig::default()
+        };
+        let script = render_shell_script(&config, None);
+        assert!(!script.contains("ghp_abc"), "{}", script);
+        assert!(script.contains(": \"${GITHUB_TOKEN:?set GITHUB_TOKEN before running}\"\n"), "{}", script);
+        assert!(script.contains("export HTTP_PROXY='http://proxy:3128'\n"), "{}", script);
+    }
+
+    #[test]
+    fn script_exports_the_run_environment() {
+        let config = ProvisioningConfig {
+            ip_address: "203.0.113.10".to_string(),
+            connection_password: "hunter2".to_string(),
+            env_vars: vec![("HTTP_PROXY".to_string(), "http://proxy:3128".to_string())],
+            ..ProvisioningConfig::default()
+        };
+        let script = render_shell_script(&config, None);
+        let exported: Vec<&str> = script.lines().filter(|l| l.starts_with("export ")).collect();
+        let expected: Vec<String> = ansible_env(&config, Path::new("/tmp/cp"))
+            .into_iter()
+            .filter(|(key, _)| key != CONN_PASS_ENV && key != CONTROL_PATH_DIR_ENV)
+            .map(|(key, value)| format!("export {}={}", key, shell_quote(&value)))
+            .collect();
+        assert_eq!(exported, expected);
+        assert!(script.contains("export ANSIBLE_SSH_RETRIES='0'\n"), "{}", script);
+        assert!(script.contains("ControlMaster="), "{}", script);
+        assert!(!script.contains("hunter2") && !script.contains(CONTROL_PATH_DIR_ENV), "{}", script);
+    }
+}
@@ -0,0 +1,255 @@
+/// Broad reason a provisioning run failed, recognised from ansible/ssh output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    VaultDecryption,
+    HostKeyVerification,
+    HostKeyChanged,
+    AuthenticationFailed,
+    SudoPasswordRequired,
+    ConnectionReset,
+    ConnectionTimedOut,
+    ConnectionRefused,
+    Unreachable,
+    TaskFailed,
+    Unknown,
+}
+
+impl FailureKind {
+    pub fn summary(&self) -> &'static str {
+        match self {
+            FailureKind::VaultDecryption => "The vault password is wrong or missing.",
+            FailureKind::HostKeyVerification => "The server's host key isn't in known_hosts.",
+            FailureKind::HostKeyChanged => "The server's host key doesn't match the one in known_hosts.",
+            FailureKind::AuthenticationFailed => "SSH rejected the credentials.",
+            FailureKind::SudoPasswordRequired => "sudo asked for a password that wasn't provided or was wrong.",
+            FailureKind::ConnectionReset => "The server dropped the SSH connection.",
+            FailureKind::ConnectionTimedOut => "The connection to the server timed out.",
+            FailureKind::ConnectionRefused => "The server refused the SSH connection.",
+            FailureKind::Unreachable => "Ansible couldn't reach the host.",
+            FailureKind::TaskFailed => "A playbook task failed on the server.",
+            FailureKind::Unknown => "The run stopped with an unrecognised error.",
+        }
+    }
+
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            FailureKind::VaultDecryption => "Enter the password the vars were encrypted with as VAULT PASSWORD under Playbook Source.",
+            FailureKind::HostKeyVerification => "Connect once with ssh to accept the key after checking its fingerprint.",
+            FailureKind::HostKeyChanged => "This can mean a man-in-the-middle attack. Confirm the new fingerprint with the server's owner before removing the old entry with ssh-keygen -R.",
+            FailureKind::AuthenticationFailed => "Check the SSH user, key path and password, and that the key is in authorized_keys.",
+            FailureKind::SudoPasswordRequired => "Enable sudo (become) in Connection and enter the user's sudo password.",
+            FailureKind::ConnectionReset => "Usually transient; raise Retries under Advanced to retry automatically.",
+            FailureKind::ConnectionTimedOut => "Verify the IP address and that port 22 is open in the firewall or security group.",
+            FailureKind::ConnectionRefused => "Make sure sshd is running and listening on the expected port.",
+            FailureKind::Unreachable => "Run Test Connection to see the underlying SSH error.",
+            FailureKind::TaskFailed => "Scroll to the first FAILED! line in the log to see which task broke.",
+            FailureKind::Unknown => "See the raw log below for details.",
+        }
+    }
+
+    /// Network hiccups worth retrying automatically, unlike credential or playbook errors.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, FailureKind::ConnectionReset | FailureKind::ConnectionTimedOut)
+    }
+}
+
+/// Picks the most specific failure reason found in the run's output.
+///
+/// Connection-level causes win over the generic `UNREACHABLE!`/`FAILED!` markers
+/// that ansible prints around them.
+pub fn classify_failure(lines: &[String]) -> FailureKind {
+    let has = |needle: &str| lines.iter().any(|l| l.contains(needle));
+
+    if has("Decryption failed") || has("no vault secrets") {
+        FailureKind::VaultDecryption
+    } else if has("REMOTE HOST IDENTIFICATION HAS CHANGED") {
+        FailureKind::HostKeyChanged
+    } else if has("Host key verification failed") {
+        FailureKind::HostKeyVerification
+    } else if has("Permission denied (") || has("Authentication failed") || has("Invalid/incorrect password") {
+        FailureKind::AuthenticationFailed
+    } else if has("Missing sudo password") || has("Incorrect sudo password") || has("sudo: a password is required") {
+        FailureKind::SudoPasswordRequired
+    } else if has("Connection reset by peer") || has("kex_exchange_identification") || has("Connection closed by") {
+        FailureKind::ConnectionReset
+    } else if has("Connection timed out") || has("Operation timed out") {
+        FailureKind::ConnectionTimedOut
+    } else if has("Connection refused") {
+        FailureKind::ConnectionRefused
+    } else if has("UNREACHABLE!") {
+        FailureKind::Unreachable
+    } else if has("FAILED!") || has("fatal:") {
+        FailureKind::TaskFailed
+    } else {
+        FailureKind::Unknown
+    }
+}
+
+/// The first task that failed (and wasn't ignored) in a run's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedTask {
+    /// Task name as ansible prints it, e.g. `docker : Add Docker GPG key`.
+    pub task: String,
+    pub host: String,
+    pub message: Option<String>,
+}
+
+impl FailedTask {
+    /// Role of a `role : task` name, without a collection/galaxy namespace.
+    pub fn role(&self) -> Option<&str> {
+        let (role, _) = self.task.split_once(" : ")?;
+        Some(role.rsplit('.').next().unwrap_or(role).trim())
+    }
+}
+
+/// Finds the first `fatal:`/`failed:` result in ANSI-stripped output, skipping failures
+/// ansible reports as `...ignoring`.
+pub fn first_failed_task(lines: &[String]) -> Option<FailedTask> {
+    let mut task = None;
+    let mut pending: Option<FailedTask> = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("TASK [") {
+            if pending.is_some() {
+                break;
+            }
+            task = rest.rfind(']').map(|end| rest[..end].trim().to_string());
+        } else if trimmed == "...ignoring" {
+            pending = None;
+        } else if let Some(rest) = trimmed.strip_prefix("fatal: [").or_else(|| trimmed.strip_prefix("failed: [")) {
+            if pending.is_none() {
+                let host = rest.split(']').next().unwrap_or_default().to_string();
+                pending = Some(FailedTask { task: task.clone().unwrap_or_default(), host, message: json_msg(rest) });
+            }
+        } else if let Some(failed) = pending.as_mut().filter(|f| f.message.is_none()) {
+            // The YAML result format puts `msg:` on its own line below the fatal one.
+            if let Some(msg) = trimmed.strip_prefix("msg:") {
+                failed.message = Some(msg.trim().trim_matches(['\'', '"']).to_string());
+            }
+        }
+    }
+    pending.filter(|f| !f.task.is_empty())
+}
+
+/// `"msg": "…"` from a one-line JSON result.
+fn json_msg(line: &str) -> Option<String> {
+    let start = line.find("\"msg\": \"")? + "\"msg\": \"".len();
+    let rest = &line[start..];
+    let end = rest.find("\", \"").or_else(|| rest.rfind('"'))?;
+    Some(rest[..end].to_string())
+}
+
+/// First tag of `role` in the playbook's `roles:` lists, read line by line.
+pub fn role_tag(playbook: &str, role: &str) -> Option<String> {
+    let mut lines = playbook.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let Some(name) = line.strip_prefix("- role:") else { continue };
+        if name.trim().rsplit('.').next() != Some(role) {
+            continue;
+        }
+        for line in lines.by_ref() {
+            if line.starts_with("- role:") {
+                return None;
+            }
+            if let Some(tags) = line.strip_prefix("tags:") {
+                let inline = tags.trim().trim_matches(['[', ']']);
+                if !inline.is_empty() {
+                    return inline.split(',').next().map(|t| t.trim().to_string());
+                }
+                return lines.next().and_then(|l| l.strip_prefix("- ")).map(|t| t.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn unreachable_timeout() {
+        let out = lines(r#"TASK [Gathering Facts] *********************************************************
+fatal: [203.0.113.10]: UNREACHABLE! => {"changed": false, "msg": "Failed to connect to the host via ssh: ssh: connect to host 203.0.113.10 port 22: Connection timed out", "unreachable": true}"#);
+        assert_eq!(classify_failure(&out), FailureKind::ConnectionTimedOut);
+    }
+
+    #[test]
+    fn unreachable_without_cause() {
+        let out = lines(r#"fatal: [web1]: UNREACHABLE! => {"changed": false, "msg": "Data could not be sent to remote host \"web1\".", "unreachable": true}"#);
+        assert_eq!(classify_failure(&out), FailureKind::Unreachable);
+    }
+
+    #[test]
+    fn refused_and_reset() {
+        let refused = lines(r#"fatal: [web1]: UNREACHABLE! => {"changed": false, "msg": "Failed to connect to the host via ssh: ssh: connect to host web1 port 2222: Connection refused", "unreachable": true}"#);
+        assert_eq!(classify_failure(&refused), FailureKind::ConnectionRefused);
+        let reset = lines(r#"fatal: [web1]: UNREACHABLE! => {"changed": false, "msg": "Failed to connect to the host via ssh: kex_exchange_identification: read: Connection reset by peer", "unreachable": true}"#);
+        assert_eq!(classify_failure(&reset), FailureKind::ConnectionReset);
+    }
+
+    #[test]
+    fn ssh_auth_failure() {
+        let out = lines(r#"fatal: [web1]: UNREACHABLE! => {"changed": false, "msg": "Failed to connect to the host via ssh: root@203.0.113.10: Permission denied (publickey,password).", "unreachable": true}"#);
+        assert_eq!(classify_failure(&out), FailureKind::AuthenticationFailed);
+    }
+
+    #[test]
+    fn host_key_changed() {
+        let out = lines("fatal: [web1]: UNREACHABLE! => {\"changed\": false, \"msg\": \"Failed to connect to the host via ssh: @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@\\r\\n@    WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED!     @\\r\\nHost key verification failed.\", \"unreachable\": true}");
+        assert_eq!(classify_failure(&out), FailureKind::HostKeyChanged);
+    }
+
+    #[test]
+    fn host_key_unknown() {
+        let out = lines(r#"fatal: [web1]: UNREACHABLE! => {"changed": false, "msg": "Failed to connect to the host via ssh: No ED25519 host key is known for 203.0.113.10 and you have requested strict checking.\r\nHost key verification failed.", "unreachable": true}"#);
+        assert_eq!(classify_failure(&out), FailureKind::HostKeyVerification);
+    }
+
+    #[test]
+    fn become_password() {
+        let missing = lines(r#"fatal: [web1]: FAILED! => {"msg": "Missing sudo password"}"#);
+        assert_eq!(classify_failure(&missing), FailureKind::SudoPasswordRequired);
+        let wrong = lines(r#"fatal: [web1]: FAILED! => {"msg": "Incorrect sudo password"}"#);
+        assert_eq!(classify_failure(&wrong), FailureKind::SudoPasswordRequired);
+    }
+
+    #[test]
+    fn vault_decryption() {
+        let out = lines("ERROR! Decryption failed (no vault secrets were found that could decrypt) on /srv/playbook/group_vars/all/vault.yml");
+        assert_eq!(classify_failure(&out), FailureKind::VaultDecryption);
+    }
+
+    #[test]
+    fn apt_lock_is_a_task_failure() {
+        let out = lines(r#"TASK [base_setup : Install base packages] **************************************
+fatal: [web1]: FAILED! => {"changed": false, "msg": "Failed to lock apt for exclusive operation: Failed to lock directory /var/lib/apt/lists/: E:Could not get lock /var/lib/apt/lists/lock. It is held by process 1234 (apt-get)"}"#);
+        assert_eq!(classify_failure(&out), FailureKind::TaskFailed);
+        let task = first_failed_task(&out).expect("failed task");
+        assert_eq!(task.task, "base_setup : Install base packages");
+        assert_eq!(task.host, "web1");
+        assert_eq!(task.role(), Some("base_setup"));
+    }
+
+    #[test]
+    fn successful_run_is_unknown() {
+        let out = lines("PLAY RECAP *********************************************************************\nweb1 : ok=12 changed=3 unreachable=0 failed=0 skipped=1 rescued=0 ignored=0");
+        assert_eq!(classify_failure(&out), FailureKind::Unknown);
+    }
+
+    #[test]
+    fn ignored_failures_are_skipped() {
+        let out = lines(r#"TASK [Check optional service] **************************************************
+fatal: [web1]: FAILED! => {"changed": false, "msg": "Could not find the requested service foo"}
+...ignoring
+TASK [docker : Add Docker GPG key] *********************************************
+fatal: [web1]: FAILED! => {"changed": false, "msg": "Failed to download key", "status": -1}"#);
+        let task = first_failed_task(&out).expect("failed task");
+        assert_eq!(task.task, "docker : Add Docker GPG key");
+        assert_eq!(task.message.as_deref(), Some("Failed to download key"));
+    }
+}
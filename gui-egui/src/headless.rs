@@ -0,0 +1,78 @@
+use crate::config::{load_cache, load_config_file, ProvisioningConfig};
+use crate::style::ansible_line_style;
+use crate::{control_path_dir, run_provisioning, secret_file, ProvisioningMessage};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+fn load(config_path: Option<&Path>) -> Result<ProvisioningConfig, String> {
+    let (config, reset) = match config_path {
+        Some(path) => load_config_file(path)?,
+        None => load_cache()?,
+    };
+    if !reset.is_empty() {
+        log::warn!("reset unreadable settings to defaults: {}", reset.join(", "));
+    }
+    Ok(config)
+}
+
+/// Colors a stripped line like the GUI does, using 24-bit ANSI escapes.
+fn colorize(line: &str) -> String {
+    let (color, bold) = ansible_line_style(line);
+    let weight = if bold { "1;" } else { "" };
+    format!("\x1b[{}38;2;{};{};{}m{}\x1b[0m", weight, color.r(), color.g(), color.b(), line)
+}
+
+/// Runs one provisioning pass without a window, streaming output to stdout. Returns the
+/// process exit code: ansible's own, or 1 if the run couldn't start.
+pub fn run(config_path: Option<&Path>, shutdown: Arc<AtomicBool>) -> i32 {
+    let config = match load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+    };
+    let raw = config.raw_output;
+    let color = std::io::stdout().is_terminal();
+
+    let (tx, rx) = channel();
+    let child_pid = Arc::new(AtomicU32::new(0));
+    let worker = std::thread::spawn(move || match tokio::runtime::Runtime::new() {
+        Ok(rt) => {
+            if let Err(e) = rt.block_on(run_provisioning(config, tx.clone(), shutdown, child_pid)) {
+                let _ = tx.send(ProvisioningMessage::Error(e));
+                let _ = tx.send(ProvisioningMessage::Complete(false));
+            }
+            rt.shutdown_timeout(std::time::Duration::from_secs(5));
+        }
+        Err(e) => {
+            let _ = tx.send(ProvisioningMessage::Error(format!("Failed to create Tokio runtime: {}", e)));
+            let _ = tx.send(ProvisioningMessage::Complete(false));
+        }
+    });
+
+    let mut exit_code = None;
+    let mut stdout = std::io::stdout().lock();
+    for msg in rx {
+        match msg {
+            ProvisioningMessage::Output(line) if raw || !color => { let _ = writeln!(stdout, "{}", line); }
+            ProvisioningMessage::Output(line) => { let _ = writeln!(stdout, "{}", colorize(&line)); }
+            ProvisioningMessage::Error(e) => eprintln!("error: {}", e),
+            ProvisioningMessage::Exit(code) => exit_code = Some(code),
+            ProvisioningMessage::Complete(success) => {
+                exit_code = exit_code.or(Some(if success { 0 } else { 1 }));
+                break;
+            }
+            ProvisioningMessage::TaskTotal(_) | ProvisioningMessage::LogFile(_) | ProvisioningMessage::Retrying => {}
+        }
+    }
+    let _ = stdout.flush();
+    let _ = worker.join();
+
+    let _ = std::fs::remove_dir_all(control_path_dir());
+    secret_file::shred_leftovers();
+    exit_code.unwrap_or(1)
+}
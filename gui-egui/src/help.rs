@@ -0,0 +1,39 @@
+//! One-line explanations of the feature toggles, keyed by config field name.
+
+pub struct FeatureHelp {
+    pub summary: &'static str,
+    /// Extra var the toggle sets.
+    pub variable: &'static str,
+    /// Upstream project page.
+    pub url: Option<&'static str>,
+}
+
+const fn help(summary: &'static str, variable: &'static str, url: Option<&'static str>) -> FeatureHelp {
+    FeatureHelp { summary, variable, url }
+}
+
+const HELP: [(&str, FeatureHelp); 18] = [
+    ("docker", help("Installs Docker Engine and the Compose plugin from Docker's apt repository.", "prompt_install_docker", Some("https://docs.docker.com/engine/"))),
+    ("lemp", help("Installs Nginx, MySQL and PHP-FPM for hosting PHP sites.", "prompt_install_lemp", Some("https://nginx.org/"))),
+    ("wordpress", help("Downloads WordPress and serves it through the LEMP stack.", "prompt_install_wordpress", Some("https://wordpress.org/"))),
+    ("certbot", help("Requests Let's Encrypt TLS certificates and sets up their renewal.", "prompt_install_certbot", Some("https://certbot.eff.org/"))),
+    ("devtools", help("Installs the selected editors, shells and command-line tools.", "prompt_install_dev_tools", None)),
+    ("fail2ban", help("Bans IP addresses after repeated failed SSH logins.", "prompt_enable_fail2ban", Some("https://github.com/fail2ban/fail2ban"))),
+    ("apparmor", help("Confines programs to per-application security profiles.", "enable_apparmor", Some("https://apparmor.net/"))),
+    ("rootkit_detection", help("Scans for rootkits and suspicious files with rkhunter.", "enable_rkhunter", Some("https://rkhunter.sourceforge.net/"))),
+    ("suricata", help("Watches network traffic for known attack signatures.", "enable_suricata", Some("https://suricata.io/"))),
+    ("disable_ipv6", help("Turns IPv6 off in the kernel for servers that only use IPv4.", "disable_ipv6", None)),
+    ("advanced_protection", help("Requires an authenticator app (TOTP) code in addition to the key or password for SSH logins.", "enable_ssh_2fa", None)),
+    ("usb_restrictions", help("Blacklists the usb-storage kernel module so USB drives can't be mounted.", "enable_usb_restrictions", None)),
+    ("backups", help("Installs a backup script and runs it from cron.", "enable_backups", None)),
+    ("system_hardening", help("Applies hardened sysctl settings for the network stack and kernel.", "enable_kernel_hardening", None)),
+    ("file_integrity", help("Records file checksums with AIDE and reports unexpected changes.", "enable_aide", Some("https://aide.github.io/"))),
+    ("audit_logging", help("Logs security-relevant system calls and file access with auditd.", "enable_auditd", Some("https://github.com/linux-audit/audit-userspace"))),
+    ("swap", help("Creates a swap file so the server doesn't run out of memory under load.", "prompt_enable_swap", None)),
+    ("cron_jobs", help("Schedules unattended security upgrades, package updates and cleanup.", "prompt_enable_cron_jobs", None)),
+];
+
+/// Help for the toggle behind config field `feature`.
+pub fn feature_help(feature: &str) -> Option<&'static FeatureHelp> {
+    HELP.iter().find(|(key, _)| *key == feature).map(|(_, help)| help)
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::PathBuf;
+use sysinfo::{Pid, System};
+
+/// Lock file recording the PID of the running GUI. Removed on drop if it still names us.
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let ours = fs::read_to_string(&self.path)
+            .map(|c| c.trim() == std::process::id().to_string())
+            .unwrap_or(false);
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn lock_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".rustsible-gui.lock"))
+}
+
+/// True if `pid` is alive and runs the same executable as this process.
+fn is_running_instance(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    if !system.refresh_process(pid) {
+        return false;
+    }
+    let our_exe = std::env::current_exe().ok();
+    let our_name = our_exe.as_ref().and_then(|p| p.file_name());
+    system
+        .process(pid)
+        .and_then(|p| p.exe())
+        .and_then(|exe| exe.file_name())
+        .is_some_and(|name| Some(name) == our_name)
+}
+
+/// Writes our PID into the lock file, replacing whatever was there.
+pub fn take_over() -> Option<InstanceLock> {
+    let path = lock_path()?;
+    fs::write(&path, std::process::id().to_string()).ok()?;
+    Some(InstanceLock { path })
+}
+
+/// Tries to become the single running instance.
+///
+/// Returns `Err(pid)` when another live instance holds the lock, and `Ok(None)` when
+/// the lock file can't be written (no home directory), in which case we run unlocked.
+pub fn acquire() -> Result<Option<InstanceLock>, u32> {
+    let Some(path) = lock_path() else { return Ok(None) };
+    if let Some(pid) = fs::read_to_string(&path).ok().and_then(|c| c.trim().parse::<u32>().ok()) {
+        if is_running_instance(pid) {
+            return Err(pid);
+        }
+    }
+    Ok(take_over())
+}
+
+/// Records the `ansible-playbook` child of the current run, so a crash doesn't lose track of it.
+fn child_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".rustsible-gui.child"))
+}
+
+/// Start time of `pid` if it is alive and runs `ansible-playbook`.
+fn ansible_start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    if !system.refresh_process(pid) {
+        return None;
+    }
+    let process = system.process(pid)?;
+    let is_ansible = process.name().starts_with("ansible-playboo") || process.cmd().iter().any(|arg| arg.ends_with("ansible-playbook"));
+    is_ansible.then(|| process.start_time())
+}
+
+/// Writes the spawned child's PID and start time to the child file.
+pub fn record_child(pid: u32) {
+    let (Some(path), Some(started)) = (child_path(), ansible_start_time(pid)) else { return };
+    let _ = fs::write(path, format!("{} {}", pid, started));
+}
+
+/// Removes the child file once the child has exited.
+pub fn clear_child() {
+    if let Some(path) = child_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// PID of the last run's `ansible-playbook` if it outlived the GUI. Only the recorded
+/// process counts: a reused PID has a different start time. Stale records are removed.
+pub fn orphaned_child() -> Option<u32> {
+    let contents = fs::read_to_string(child_path()?).ok()?;
+    let (pid, started) = contents.trim().split_once(' ')?;
+    let (pid, started) = (pid.parse::<u32>().ok()?, started.parse::<u64>().ok()?);
+    if ansible_start_time(pid) == Some(started) {
+        Some(pid)
+    } else {
+        clear_child();
+        None
+    }
+}
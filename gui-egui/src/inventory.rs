@@ -0,0 +1,222 @@
+use crate::config::ProvisioningConfig;
+use std::collections::HashMap;
+
+/// Connection details for one host from an INI inventory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventoryHost {
+    /// Inventory name, which is also the address when `ansible_host` is unset.
+    pub name: String,
+    pub group: String,
+    pub address: String,
+    pub user: Option<String>,
+    pub key_file: Option<String>,
+    pub port: Option<u16>,
+}
+
+/// Splits `key=value` tokens, honouring single and double quotes around values.
+fn parse_vars(text: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let Some((key, after)) = rest.split_once('=') else { break };
+        let key = key.trim().to_string();
+        let after = after.trim_start();
+        let (value, remaining) = match after.chars().next() {
+            Some(q @ ('"' | '\'')) => match after[1..].find(q) {
+                Some(end) => (&after[1..end + 1], &after[end + 2..]),
+                None => (&after[1..], ""),
+            },
+            _ => match after.find(char::is_whitespace) {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            },
+        };
+        if !key.is_empty() && !key.contains(char::is_whitespace) {
+            vars.insert(key, value.to_string());
+        }
+        rest = remaining.trim_start();
+    }
+    vars
+}
+
+fn host_from_vars(name: &str, group: &str, vars: &HashMap<String, String>) -> InventoryHost {
+    InventoryHost {
+        name: name.to_string(),
+        group: group.to_string(),
+        address: vars.get("ansible_host").cloned().unwrap_or_else(|| name.to_string()),
+        user: vars.get("ansible_user").or_else(|| vars.get("ansible_ssh_user")).cloned(),
+        key_file: vars.get("ansible_ssh_private_key_file").or_else(|| vars.get("ansible_private_key_file")).cloned(),
+        port: vars.get("ansible_port").or_else(|| vars.get("ansible_ssh_port")).and_then(|p| p.parse().ok()),
+    }
+}
+
+/// Parses an INI-style ansible inventory into its hosts.
+///
+/// Host lines inherit `[group:vars]` and `[all:vars]` values, with the host's own
+/// `key=value` pairs taking precedence. `[group:children]` sections are ignored.
+pub fn parse_inventory(text: &str) -> Vec<InventoryHost> {
+    enum Section { Hosts(String), Vars(String), Other }
+
+    let mut section = Section::Hosts("ungrouped".to_string());
+    let mut host_lines: Vec<(String, String, HashMap<String, String>)> = Vec::new();
+    let mut group_vars: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = match header.split_once(':') {
+                Some((group, "vars")) => Section::Vars(group.to_string()),
+                Some(_) => Section::Other,
+                None => Section::Hosts(header.to_string()),
+            };
+            continue;
+        }
+        match &section {
+            Section::Hosts(group) => {
+                let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                host_lines.push((name.to_string(), group.clone(), parse_vars(rest)));
+            }
+            Section::Vars(group) => {
+                group_vars.entry(group.clone()).or_default().extend(parse_vars(line));
+            }
+            Section::Other => {}
+        }
+    }
+
+    host_lines
+        .into_iter()
+        .map(|(name, group, own)| {
+            let mut vars = group_vars.get("all").cloned().unwrap_or_default();
+            if let Some(group_specific) = group_vars.get(&group) {
+                vars.extend(group_specific.clone());
+            }
+            vars.extend(own);
+            host_from_vars(&name, &group, &vars)
+        })
+        .collect()
+}
+
+/// Renders the inventory a run implies: every target host in `provisioning_target` with
+/// its connection vars.
+pub fn render_inventory(config: &ProvisioningConfig) -> String {
+    let mut text = String::from("# Play 1 adds these hosts to provisioning_target again with the GUI's connection\n");
+    text.push_str("# settings; extra host vars and groups added here are kept.\n");
+    text.push_str("[provisioning_target]\n");
+    for host in config.target_hosts() {
+        text.push_str(&format!("{} ansible_port={} ansible_user={}\n", host, config.ssh_port_for(&host), config.ssh_user_for(&host)));
+    }
+    text
+}
+
+/// Checks an edited INI inventory for lines ansible's ini plugin would reject.
+pub fn validate_inventory(text: &str) -> Result<(), String> {
+    let mut in_vars = false;
+    let mut hosts = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let fail = |msg: &str| Err(format!("Inventory line {}: {}", i + 1, msg));
+        if line.starts_with('[') {
+            let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) else {
+                return fail("section header is missing ']'");
+            };
+            let (group, kind) = header.split_once(':').unwrap_or((header, ""));
+            if group.is_empty() || group.contains(char::is_whitespace) {
+                return fail("group names can't be empty or contain spaces");
+            }
+            if !matches!(kind, "" | "vars" | "children") {
+                return fail("sections must be [group], [group:vars] or [group:children]");
+            }
+            in_vars = kind == "vars";
+            continue;
+        }
+        let (name, rest) = if in_vars { ("", line) } else { line.split_once(char::is_whitespace).unwrap_or((line, "")) };
+        let rest = rest.trim();
+        if !rest.is_empty() && parse_vars(rest).is_empty() {
+            return fail("expected key=value");
+        }
+        if !name.is_empty() {
+            hosts += 1;
+        }
+    }
+    if hosts == 0 {
+        return Err("Inventory has no hosts".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTI_GROUP: &str = r#"# Production inventory
+bastion.example.com ansible_host=198.51.100.5
+
+[web]
+web1 ansible_host=203.0.113.10 ansible_port=2222
+web2 ansible_host=203.0.113.11 ansible_user='deploy user'
+
+[db]
+db1.internal ansible_user=postgres ansible_ssh_port=5022 ansible_ssh_private_key_file="~/.ssh/db key"
+
+[prod:children]
+web
+db
+
+[all:vars]
+ansible_user=admin
+ansible_ssh_private_key_file=~/.ssh/prod_ed25519
+
+[web:vars]
+ansible_port=22
+http_port=8080
+"#;
+
+    fn host<'a>(hosts: &'a [InventoryHost], name: &str) -> &'a InventoryHost {
+        hosts.iter().find(|h| h.name == name).unwrap_or_else(|| panic!("{} not parsed", name))
+    }
+
+    #[test]
+    fn parses_multi_group_inventory() {
+        let hosts = parse_inventory(MULTI_GROUP);
+        // `[prod:children]` lists groups, not hosts.
+        let names: Vec<&str> = hosts.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["bastion.example.com", "web1", "web2", "db1.internal"]);
+
+        assert_eq!(host(&hosts, "bastion.example.com"), &InventoryHost {
+            name: "bastion.example.com".to_string(),
+            group: "ungrouped".to_string(),
+            address: "198.51.100.5".to_string(),
+            user: Some("admin".to_string()),
+            key_file: Some("~/.ssh/prod_ed25519".to_string()),
+            port: None,
+        });
+        // Host vars beat [web:vars], which beat [all:vars].
+        let web1 = host(&hosts, "web1");
+        assert_eq!((web1.group.as_str(), web1.address.as_str(), web1.port), ("web", "203.0.113.10", Some(2222)));
+        assert_eq!(web1.user.as_deref(), Some("admin"));
+        let web2 = host(&hosts, "web2");
+        assert_eq!((web2.port, web2.user.as_deref()), (Some(22), Some("deploy user")));
+
+        let db1 = host(&hosts, "db1.internal");
+        assert_eq!(db1.group, "db");
+        assert_eq!(db1.address, "db1.internal");
+        assert_eq!(db1.user.as_deref(), Some("postgres"));
+        assert_eq!(db1.key_file.as_deref(), Some("~/.ssh/db key"));
+        assert_eq!(db1.port, Some(5022));
+    }
+
+    #[test]
+    fn validates_multi_group_inventory() {
+        assert_eq!(validate_inventory(MULTI_GROUP), Ok(()));
+        assert!(validate_inventory("[web:hosts]\nweb1\n").is_err());
+        assert!(validate_inventory("[web\nweb1\n").is_err());
+        assert!(validate_inventory("[web]\nweb1 not-a-var\n").is_err());
+        assert!(validate_inventory("[all:vars]\nansible_user=admin\n").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Line appended to a run log once the run ends, however it ends. A log without it (or a
+/// recap) was cut off by a crash or force-quit.
+const RUN_END_MARKER: &str = "# rustsible: run ended";
+
+/// `~/.rustsible/logs`, where each provisioning run gets its own transcript.
+pub fn logs_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".rustsible").join("logs"))
+}
+
+fn is_run_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with("provision-") && n.ends_with(".log"))
+}
+
+/// Run logs in `dir`, newest first. The timestamped names sort chronologically.
+fn run_logs_in(dir: &Path) -> Vec<PathBuf> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| is_run_log(p)).collect())
+        .unwrap_or_default();
+    logs.sort();
+    logs.reverse();
+    logs
+}
+
+/// Past run logs, newest first.
+pub fn list_run_logs() -> Vec<PathBuf> {
+    logs_dir().map(|dir| run_logs_in(&dir)).unwrap_or_default()
+}
+
+/// Deletes all but the newest `keep` run logs in `dir`.
+fn prune_logs(dir: &Path, keep: usize) {
+    for old in run_logs_in(dir).into_iter().skip(keep) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// Creates `provision-<timestamp>.log` for a new run and prunes older logs so that at
+/// most `retention` remain, including the new one.
+pub fn create_run_log(retention: usize) -> Result<(PathBuf, fs::File), String> {
+    let dir = logs_dir().ok_or("Could not determine home directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let mut path = dir.join(format!("provision-{}.log", stamp));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("provision-{}-{}.log", stamp, n));
+        n += 1;
+    }
+    let file = fs::File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    prune_logs(&dir, retention.max(1));
+    Ok((path, file))
+}
+
+/// Appends the end-of-run marker with a short reason such as `exit 0` or `terminated`.
+pub fn mark_run_ended(file: &mut fs::File, reason: &str) {
+    let _ = writeln!(file, "{} ({})", RUN_END_MARKER, reason);
+}
+
+fn run_ended(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|text| text.lines().any(|l| l.starts_with(RUN_END_MARKER) || l.starts_with("PLAY RECAP")))
+        .unwrap_or(true)
+}
+
+/// The newest run log if that run never finished, e.g. because the app was killed mid-run.
+pub fn interrupted_run() -> Option<PathBuf> {
+    list_run_logs().into_iter().next().filter(|path| !run_ended(path))
+}
+
+/// Marks an interrupted log as handled so it isn't offered again on the next start.
+pub fn dismiss_interrupted(path: &Path) {
+    if let Ok(mut file) = fs::OpenOptions::new().append(true).open(path) {
+        mark_run_ended(&mut file, "interrupted");
+    }
+}
@@ -0,0 +1,15 @@
+use notify_rust::Notification;
+
+/// Posts a desktop notification for a finished run. Sent from a background thread since
+/// some notification daemons answer slowly.
+pub fn provisioning_finished(success: bool, hosts: &[String]) {
+    let body = match (success, hosts.join(", ")) {
+        (true, hosts) if !hosts.is_empty() => format!("Provisioning succeeded on {}", hosts),
+        (true, _) => "Provisioning succeeded".to_string(),
+        (false, hosts) if !hosts.is_empty() => format!("Provisioning failed on {}", hosts),
+        (false, _) => "Provisioning failed".to_string(),
+    };
+    let _ = std::thread::Builder::new().name("notification".into()).spawn(move || {
+        let _ = Notification::new().summary("Rustsible").body(&body).show();
+    });
+}
@@ -0,0 +1,503 @@
+use crate::command::{parse_extra_vars, validate_arg_values, validate_env_var};
+use crate::config::{expand_tilde, HostEntry, ProvisioningConfig, FORKS_RANGE, SWAP_SIZE_RANGE_MB};
+use crate::inventory::validate_inventory;
+use crate::secret_file::validate_private_key_text;
+use std::path::{Path, PathBuf};
+
+/// Problems with the configured SSH private key that would make ssh refuse it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyIssue {
+    Missing(PathBuf),
+    IsDirectory(PathBuf),
+    PublicKey(PathBuf),
+    /// Group/other permission bits are set; ssh ignores such keys.
+    TooOpen(PathBuf, u32),
+}
+
+impl KeyIssue {
+    pub fn message(&self) -> String {
+        match self {
+            KeyIssue::Missing(p) => format!("Key not found: {}", p.display()),
+            KeyIssue::IsDirectory(p) => format!("{} is a directory, not a key file", p.display()),
+            KeyIssue::PublicKey(p) => format!("{} is a public key — select the private key without .pub", p.display()),
+            KeyIssue::TooOpen(p, mode) => format!("{} has permissions {:o}; run chmod 600 on it", p.display(), mode),
+        }
+    }
+}
+
+/// Checks that `path` points at something ssh will accept as a private key.
+pub fn validate_ssh_key(path: &Path) -> Result<(), KeyIssue> {
+    let metadata = std::fs::metadata(path).map_err(|_| KeyIssue::Missing(path.to_path_buf()))?;
+    if metadata.is_dir() {
+        return Err(KeyIssue::IsDirectory(path.to_path_buf()));
+    }
+    if path.extension().is_some_and(|ext| ext == "pub") {
+        return Err(KeyIssue::PublicKey(path.to_path_buf()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(KeyIssue::TooOpen(path.to_path_buf(), mode));
+        }
+    }
+    Ok(())
+}
+
+/// Validates the key path as typed in the UI; an empty path is allowed (password auth).
+pub fn validate_ssh_key_field(raw: &str) -> Result<(), KeyIssue> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(());
+    }
+    validate_ssh_key(&expand_tilde(raw))
+}
+
+/// Validates a hostname per RFC 1123: dot-separated labels of 1–63 letters, digits and
+/// hyphens, not starting or ending with a hyphen, 253 characters total. Empty is allowed.
+pub fn validate_hostname(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Ok(());
+    }
+    if name.len() > 253 {
+        return Err("Hostname must be at most 253 characters".to_string());
+    }
+    for label in name.strip_suffix('.').unwrap_or(name).split('.') {
+        if label.is_empty() {
+            return Err("Hostname has an empty label (two dots in a row?)".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("'{}' is longer than 63 characters", label));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("'{}' can't start or end with a hyphen", label));
+        }
+        if let Some(c) = label.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '-') {
+            return Err(format!("'{}' isn't allowed in a hostname; use letters, digits and hyphens", c));
+        }
+    }
+    Ok(())
+}
+
+/// Validates each target host as an IP address or RFC 1123 hostname; at least one is required.
+pub fn validate_target_hosts(hosts: &[String]) -> Result<(), String> {
+    if hosts.is_empty() {
+        return Err("IP address is required".to_string());
+    }
+    for host in hosts {
+        if host.parse::<std::net::IpAddr>().is_err() {
+            validate_hostname(host).map_err(|e| format!("{}: {}", host, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Validates the target host field: every entry must parse as `[user@]host[:port]` with a
+/// valid host or an IPv4 range of at most `MAX_CIDR_HOSTS` addresses.
+pub fn validate_host_entries(config: &ProvisioningConfig) -> Result<(), String> {
+    for entry in config.host_field_entries() {
+        HostEntry::expand(entry)?;
+    }
+    validate_target_hosts(&config.target_hosts())
+}
+
+/// Validates one cron field: `*`, `*/N`, a number, a range `a-b`, or a comma list of
+/// numbers and ranges, with every number within `min..=max`.
+pub fn validate_cron_field(field: &str, min: u32, max: u32) -> Result<(), String> {
+    let in_range = |v: &str| v.parse::<u32>().ok().filter(|n| (min..=max).contains(n));
+    let field = field.trim();
+    if field == "*" {
+        return Ok(());
+    }
+    if let Some(step) = field.strip_prefix("*/") {
+        return match step.parse::<u32>() {
+            Ok(n) if n >= 1 && n <= max => Ok(()),
+            _ => Err(format!("'{}' needs a step between 1 and {}", field, max)),
+        };
+    }
+    for part in field.split(',') {
+        let valid = match part.split_once('-') {
+            Some((a, b)) => matches!((in_range(a), in_range(b)), (Some(a), Some(b)) if a <= b),
+            None => in_range(part).is_some(),
+        };
+        if !valid {
+            return Err(format!("'{}' isn't a valid cron value between {} and {}", part, min, max));
+        }
+    }
+    Ok(())
+}
+
+/// Checks a name against the portable Unix username rules used by `useradd`:
+/// lowercase letters, digits, `_` and `-`, not starting with a digit or `-`, at most 32 chars.
+pub fn validate_unix_username(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Username is required".to_string());
+    }
+    if name.len() > 32 {
+        return Err("Username must be at most 32 characters".to_string());
+    }
+    let mut chars = name.chars();
+    if !chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_') {
+        return Err("Username must start with a lowercase letter or _".to_string());
+    }
+    if !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+        return Err("Username may only contain a-z, 0-9, _ and -".to_string());
+    }
+    if name == "root" {
+        return Err("Pick a user other than root".to_string());
+    }
+    Ok(())
+}
+
+/// Validates the admin user the playbook creates; nothing to check when creation is off.
+pub fn validate_user_account(create_user: bool, added_user: &str, user_password: &str) -> Result<(), String> {
+    if !create_user {
+        return Ok(());
+    }
+    validate_unix_username(added_user.trim())?;
+    if user_password.is_empty() {
+        return Err(format!("A password is required for {}", added_user.trim()));
+    }
+    Ok(())
+}
+
+fn known_hosts_path() -> Result<PathBuf, String> {
+    Ok(dirs::home_dir().ok_or("Could not determine home directory")?.join(".ssh").join("known_hosts"))
+}
+
+/// How `known_hosts` names `host`: bare on port 22, `[host]:port` otherwise.
+fn known_hosts_entry(host: &str, port: u16) -> String {
+    if port == 22 { host.to_string() } else { format!("[{}]:{}", host, port) }
+}
+
+/// Keys a host presented to one `ssh-keyscan`, as hashed `known_hosts` lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedHostKeys {
+    /// The host as `known_hosts` names it.
+    pub host: String,
+    /// `TYPE SHA256:...` per key in `lines`.
+    pub fingerprints: Vec<String>,
+    lines: Vec<u8>,
+}
+
+/// Fetches the keys `host` presents now, for the user to check before trusting them.
+pub fn scan_host_keys(host: &str, port: u16) -> Result<ScannedHostKeys, String> {
+    let scan = std::process::Command::new("ssh-keyscan")
+        .args(["-T", "5", "-H", "-p", &port.to_string(), host])
+        .output()
+        .map_err(|e| format!("Failed to run ssh-keyscan: {}", e))?;
+    let fingerprints = key_fingerprints(&scan.stdout)?;
+    if fingerprints.is_empty() {
+        return Err(format!("ssh-keyscan returned no keys for {}", host));
+    }
+    Ok(ScannedHostKeys { host: known_hosts_entry(host, port), fingerprints, lines: scan.stdout })
+}
+
+/// Whether `known_hosts` already has an entry for `host`, matching or not.
+pub fn is_known_host(host: &str, port: u16) -> bool {
+    known_hosts_path().is_ok_and(|path| !recorded_fingerprints(&path, &known_hosts_entry(host, port)).is_empty())
+}
+
+/// Appends exactly the scanned keys the user was shown to `known_hosts`.
+///
+/// Only for hosts with no entry yet: an existing entry is never replaced here, since a
+/// mismatch is exactly the change ssh warns about.
+pub fn trust_host_key(scan: &ScannedHostKeys) -> Result<(), String> {
+    let known_hosts = known_hosts_path()?;
+    if let Some(ssh_dir) = known_hosts.parent() {
+        std::fs::create_dir_all(ssh_dir).map_err(|e| format!("Failed to create {}: {}", ssh_dir.display(), e))?;
+    }
+    if !recorded_fingerprints(&known_hosts, &scan.host).is_empty() {
+        return Err(format!("known_hosts already has an entry for {}; review it with ssh-keygen -F before changing it", scan.host));
+    }
+
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&known_hosts)
+        .map_err(|e| format!("Failed to open {}: {}", known_hosts.display(), e))?;
+    file.write_all(&scan.lines).map_err(|e| format!("Failed to update {}: {}", known_hosts.display(), e))
+}
+
+/// Fingerprints of the key `known_hosts` holds for a host and of the one it presents now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostKeyFingerprints {
+    pub host: String,
+    /// `TYPE SHA256:...` per recorded key.
+    pub recorded: Vec<String>,
+    /// `TYPE SHA256:...` per key the host offers, or why they couldn't be fetched.
+    pub presented: Result<Vec<String>, String>,
+}
+
+/// Looks up both sides of a changed host key so the user can compare them; read-only.
+pub fn host_key_fingerprints(host: &str, port: u16) -> HostKeyFingerprints {
+    let entry = known_hosts_entry(host, port);
+    let recorded = known_hosts_path().map(|path| recorded_fingerprints(&path, &entry)).unwrap_or_default();
+    HostKeyFingerprints { host: entry, recorded, presented: scan_host_keys(host, port).map(|scan| scan.fingerprints) }
+}
+
+fn recorded_fingerprints(known_hosts: &Path, entry: &str) -> Vec<String> {
+    std::process::Command::new("ssh-keygen")
+        .arg("-l")
+        .arg("-F")
+        .arg(entry)
+        .arg("-f")
+        .arg(known_hosts)
+        .output()
+        .map(|out| fingerprint_lines(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default()
+}
+
+/// Fingerprints of `known_hosts`-format key lines, via `ssh-keygen -l`.
+fn key_fingerprints(lines: &[u8]) -> Result<Vec<String>, String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("ssh-keygen")
+        .args(["-l", "-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run ssh-keygen: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(lines);
+    }
+    let out = child.wait_with_output().map_err(|e| format!("Failed to run ssh-keygen: {}", e))?;
+    Ok(fingerprint_lines(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// `TYPE SHA256:...` from `ssh-keygen -l` output, which ends lines with `(TYPE)` when
+/// fingerprinting keys and puts the type before the hash with `-F`.
+fn fingerprint_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let at = fields.iter().position(|f| f.starts_with("SHA256:"))?;
+            let kind = fields.last().and_then(|f| f.strip_prefix('(')?.strip_suffix(')')).or_else(|| fields.get(at.checked_sub(1)?).copied())?;
+            Some(format!("{} {}", kind, fields[at]))
+        })
+        .collect()
+}
+
+/// Version details reported by `ansible-playbook --version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnsibleVersion {
+    /// First line of the output, e.g. `core 2.16.3` from `ansible-playbook [core 2.16.3]`.
+    pub version: String,
+    pub python: Option<String>,
+}
+
+/// Parses the `--version` output: the bracketed version on the first line and the
+/// `python version = ...` line, whose trailing build details are dropped.
+pub fn parse_ansible_version(output: &str) -> Option<AnsibleVersion> {
+    let first = output.lines().next()?.trim();
+    let version = match (first.find('['), first.rfind(']')) {
+        (Some(start), Some(end)) if start < end => first[start + 1..end].trim().to_string(),
+        _ => first.strip_prefix("ansible-playbook").unwrap_or(first).trim().to_string(),
+    };
+    if version.is_empty() {
+        return None;
+    }
+    let python = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("python version ="))
+        .and_then(|v| v.split_whitespace().next())
+        .map(String::from);
+    Some(AnsibleVersion { version, python })
+}
+
+/// Runs `ansible-playbook --version`; `None` when it's missing or fails.
+pub fn detect_ansible_version() -> Option<AnsibleVersion> {
+    let output = std::process::Command::new("ansible-playbook").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_ansible_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// One of the checks run before a deploy is allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreflightCheck {
+    Ansible,
+    SshKey,
+    HostReachable,
+    /// Not run automatically; recorded by Test Connection, which logs in for real.
+    Authenticated,
+}
+
+impl PreflightCheck {
+    pub const ALL: [PreflightCheck; 3] = [PreflightCheck::Ansible, PreflightCheck::SshKey, PreflightCheck::HostReachable];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PreflightCheck::Ansible => "Ansible installed",
+            PreflightCheck::SshKey => "SSH key usable",
+            PreflightCheck::HostReachable => "Host reachable",
+            PreflightCheck::Authenticated => "SSH login works",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pending,
+    Passed,
+    Failed(String),
+}
+
+/// True when `ip` is `localhost` or an address of this machine: loopback, unspecified, or
+/// assigned to a local interface (checked by binding a UDP socket to it). Other hostnames
+/// aren't resolved.
+pub fn is_local_address(ip: &str) -> bool {
+    let ip = ip.trim();
+    if ip.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    let Ok(addr) = ip.trim_start_matches('[').trim_end_matches(']').parse::<std::net::IpAddr>() else {
+        return false;
+    };
+    addr.is_loopback() || addr.is_unspecified() || std::net::UdpSocket::bind((addr, 0)).is_ok()
+}
+
+/// Opens a TCP connection to the SSH port of every host; the first unreachable one fails.
+pub fn check_hosts_reachable(targets: &[(String, u16)], timeout: std::time::Duration) -> Result<(), String> {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    validate_target_hosts(&targets.iter().map(|(host, _)| host.clone()).collect::<Vec<_>>())?;
+    for (host, port) in targets {
+        let (host, port) = (host, *port);
+        let addrs: Vec<_> = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| format!("{}: {}", host, e))?
+            .collect();
+        let mut last_err = format!("{}: no addresses", host);
+        if !addrs.iter().any(|addr| match TcpStream::connect_timeout(addr, timeout) {
+            Ok(_) => true,
+            Err(e) => {
+                last_err = format!("{}:{}: {}", host, port, e);
+                false
+            }
+        }) {
+            return Err(last_err);
+        }
+    }
+    Ok(())
+}
+
+/// A problem with the configured options. Blocking issues prevent a launch; warnings
+/// point out combinations that probably won't do what the user expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub blocking: bool,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn error(message: impl Into<String>) -> Self {
+        ConfigIssue { blocking: true, message: message.into() }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        ConfigIssue { blocking: false, message: message.into() }
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+}
+
+/// Checks the options against each other without touching the network. The only
+/// filesystem access is one `stat` of the working directory, so it is still cheap enough to
+/// run every frame. Errors come first.
+pub fn validate_config(config: &ProvisioningConfig) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut check = |result: Result<(), String>| {
+        if let Err(e) = result {
+            issues.push(ConfigIssue::error(e));
+        }
+    };
+
+    check(validate_host_entries(config));
+    if !config.jump_host.trim().is_empty() {
+        check(validate_target_hosts(&[config.jump_host.trim().to_string()]).map_err(|e| format!("Jump host: {}", e)));
+    }
+    if config.jump_user.trim().contains(|c: char| c == '@' || c.is_whitespace()) {
+        check(Err("Jump user can't contain '@' or spaces".to_string()));
+    }
+    if config.ssh_user.trim().is_empty() {
+        check(Err("SSH user is required".to_string()));
+    }
+    if config.use_inline_key {
+        check(validate_private_key_text(&config.inline_private_key));
+    } else if config.ssh_key_path.trim().is_empty() && config.connection_password.is_empty() {
+        check(Err("SSH key path or connection password is required".to_string()));
+    }
+    check(validate_arg_values(config));
+    if let Some(text) = config.edited_inventory() {
+        check(validate_inventory(text));
+    }
+    if config.connect_timeout == 0 {
+        check(Err("Connection timeout must be at least 1 second".to_string()));
+    }
+    if !config.working_dir.trim().is_empty() && !expand_tilde(&config.working_dir).is_dir() {
+        check(Err(format!("Working directory not found: {}", expand_tilde(&config.working_dir).display())));
+    }
+    if !FORKS_RANGE.contains(&config.forks) {
+        check(Err(format!("Forks must be between {} and {}", FORKS_RANGE.start(), FORKS_RANGE.end())));
+    }
+    if config.swap && config.swap_size_mb != 0 && !SWAP_SIZE_RANGE_MB.contains(&config.swap_size_mb) {
+        check(Err(format!(
+            "Swap size must be auto or between {} and {} MB",
+            SWAP_SIZE_RANGE_MB.start(),
+            SWAP_SIZE_RANGE_MB.end()
+        )));
+    }
+    if config.fail2ban {
+        for (var, value) in config.fail2ban_settings() {
+            if !value.parse::<u32>().is_ok_and(|n| n > 0) {
+                check(Err(format!("{} must be a positive whole number", var)));
+            }
+        }
+    }
+    check(validate_hostname(config.hostname.trim()));
+    if config.periodic_reboot {
+        check(validate_cron_field(&config.reboot_hour, 0, 23).map_err(|e| format!("Reboot hour: {}", e)));
+        check(validate_cron_field(&config.reboot_minute, 0, 59).map_err(|e| format!("Reboot minute: {}", e)));
+    }
+    check(validate_user_account(config.create_user, &config.added_user, &config.user_password));
+    check(parse_extra_vars(&config.extra_vars_raw).map(|_| ()));
+    for (key, value) in config.env_vars.iter().filter(|(k, v)| !k.is_empty() || !v.is_empty()) {
+        check(validate_env_var(key, value));
+    }
+
+    if config.offline_mode {
+        let online: Vec<&str> = [
+            (config.fail2ban, "Fail2ban"),
+            (config.lemp, "LEMP"),
+            (config.docker, "Docker"),
+            (config.devtools, "Dev tools"),
+            (config.wordpress, "WordPress"),
+            (config.certbot, "Certbot"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        if !online.is_empty() {
+            issues.push(ConfigIssue::warning(format!("Offline mode: {} need packages or downloads from the internet; downloads are skipped and packages must come from a local mirror", online.join(", "))));
+        }
+    }
+    if config.certbot && !config.lemp {
+        issues.push(ConfigIssue::warning("Certbot needs a web server to answer the challenge; LEMP is off"));
+    }
+    if config.wordpress && !config.lemp {
+        issues.push(ConfigIssue::warning("WordPress is served by the LEMP stack, which is off"));
+    }
+    if config.periodic_reboot && !config.cron_jobs {
+        issues.push(ConfigIssue::warning("Scheduled reboots are set up by Automated Ops, which is off"));
+    }
+    issues
+}
@@ -0,0 +1,179 @@
+use serde::Serialize;
+
+/// Per-host counters from ansible's `PLAY RECAP` block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HostRecap {
+    pub host: String,
+    pub ok: u32,
+    pub changed: u32,
+    pub unreachable: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub rescued: u32,
+    pub ignored: u32,
+}
+
+impl HostRecap {
+    pub fn has_problems(&self) -> bool {
+        self.failed > 0 || self.unreachable > 0
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RecapSummary {
+    pub hosts: Vec<HostRecap>,
+}
+
+impl RecapSummary {
+    pub fn has_problems(&self) -> bool {
+        self.hosts.iter().any(HostRecap::has_problems)
+    }
+
+    /// Names of the hosts that failed or were unreachable.
+    pub fn failed_hosts(&self) -> Vec<String> {
+        self.hosts.iter().filter(|h| h.has_problems()).map(|h| h.host.clone()).collect()
+    }
+}
+
+/// Parses a single aligned recap row such as
+/// `203.0.113.10   : ok=42   changed=10   unreachable=0    failed=0 ...`.
+fn parse_host_line(line: &str) -> Option<HostRecap> {
+    let (host, counters) = line.split_once(" : ").or_else(|| line.split_once(':'))?;
+    let host = host.trim();
+    if host.is_empty() || host.contains(char::is_whitespace) || !counters.contains("ok=") {
+        return None;
+    }
+
+    let mut recap = HostRecap { host: host.to_string(), ..Default::default() };
+    for pair in counters.split_whitespace() {
+        let Some((key, value)) = pair.split_once('=') else { continue };
+        let Ok(value) = value.parse::<u32>() else { continue };
+        match key {
+            "ok" => recap.ok = value,
+            "changed" => recap.changed = value,
+            "unreachable" => recap.unreachable = value,
+            "failed" => recap.failed = value,
+            "skipped" => recap.skipped = value,
+            "rescued" => recap.rescued = value,
+            "ignored" => recap.ignored = value,
+            _ => {}
+        }
+    }
+    Some(recap)
+}
+
+/// Tasks that reported `changed` and the hosts they changed on, in run order. In a
+/// `--check` run these are the changes a real run would make.
+pub fn changed_tasks(lines: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut tasks: Vec<(String, Vec<String>)> = Vec::new();
+    let mut current = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("TASK [") {
+            current = rest.rfind(']').map(|end| rest[..end].trim().to_string());
+        } else if let (Some(rest), Some(task)) = (trimmed.strip_prefix("changed: ["), &current) {
+            let host = rest.split(']').next().unwrap_or_default().to_string();
+            if tasks.last().is_none_or(|(name, _)| name != task) {
+                tasks.push((task.clone(), Vec::new()));
+            }
+            if let Some((_, hosts)) = tasks.last_mut().filter(|(_, hosts)| !hosts.contains(&host)) {
+                hosts.push(host);
+            }
+        }
+    }
+    tasks
+}
+
+/// Extracts the host counters from the last `PLAY RECAP` block in the output.
+pub fn parse_recap(lines: &[String]) -> Option<RecapSummary> {
+    let start = lines.iter().rposition(|l| l.trim_start().starts_with("PLAY RECAP"))?;
+
+    let mut summary = RecapSummary::default();
+    for line in &lines[start + 1..] {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if summary.hosts.is_empty() { continue } else { break }
+        }
+        match parse_host_line(trimmed) {
+            Some(host) => summary.hosts.push(host),
+            None => break,
+        }
+    }
+
+    if summary.hosts.is_empty() { None } else { Some(summary) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    fn host(name: &str, ok: u32, changed: u32, unreachable: u32, failed: u32) -> HostRecap {
+        HostRecap { host: name.to_string(), ok, changed, unreachable, failed, ..Default::default() }
+    }
+
+    #[test]
+    fn parses_every_host_in_aligned_columns() {
+        let out = lines("\
+PLAY RECAP *********************************************************************
+203.0.113.10               : ok=42   changed=10   unreachable=0    failed=0    skipped=5    rescued=1    ignored=2   
+web2                       : ok=3    changed=0    unreachable=1    failed=0    skipped=0    rescued=0    ignored=0   
+
+Saturday 17 October 2026  09:15:22 +0000 (0:00:01.250)       0:01:22.630 *******");
+        let summary = parse_recap(&out).unwrap();
+        assert_eq!(summary.hosts, vec![
+            HostRecap { skipped: 5, rescued: 1, ignored: 2, ..host("203.0.113.10", 42, 10, 0, 0) },
+            host("web2", 3, 0, 1, 0),
+        ]);
+        assert_eq!(summary.failed_hosts(), ["web2"]);
+    }
+
+    #[test]
+    fn missing_counters_default_to_zero() {
+        let recap = parse_host_line("web1 : ok=5 changed=2 unreachable=0 failed=1 skipped=3").unwrap();
+        assert_eq!(recap, HostRecap { skipped: 3, ..host("web1", 5, 2, 0, 1) });
+        assert!(recap.has_problems());
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_host_rows() {
+        assert_eq!(parse_host_line("Saturday 17 October 2026  09:15:22 +0000 (0:00:01.250)"), None);
+        assert_eq!(parse_host_line(" : ok=1"), None);
+        assert_eq!(parse_recap(&lines("PLAY RECAP ****\n\nnothing here")), None);
+        assert_eq!(parse_recap(&lines("TASK [a] ****\nok: [web1]")), None);
+    }
+
+    #[test]
+    fn uses_the_last_recap() {
+        let out = lines("\
+PLAY RECAP *********************************************************************
+web1 : ok=1 changed=0 unreachable=0 failed=1 skipped=0 rescued=0 ignored=0
+PLAY [Retry] *******************************************************************
+PLAY RECAP *********************************************************************
+web1 : ok=9 changed=4 unreachable=0 failed=0 skipped=0 rescued=0 ignored=0");
+        let summary = parse_recap(&out).unwrap();
+        assert_eq!(summary.hosts, vec![host("web1", 9, 4, 0, 0)]);
+        assert!(!summary.has_problems());
+    }
+
+    #[test]
+    fn changed_tasks_groups_hosts_in_run_order() {
+        let out = lines("\
+TASK [common : Install base packages] ******************************************
+changed: [web1]
+ok: [web2]
+changed: [web3]
+changed: [web1]
+TASK [security : Harden sshd] **************************************************
+ok: [web1]
+TASK [docker : Install Docker] *************************************************
+changed: [web2]");
+        assert_eq!(changed_tasks(&out), vec![
+            ("common : Install base packages".to_string(), vec!["web1".to_string(), "web3".to_string()]),
+            ("docker : Install Docker".to_string(), vec!["web2".to_string()]),
+        ]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config::ProvisioningConfig;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_FILE: AtomicU32 = AtomicU32::new(0);
+
+/// Prefix of this process's temporary secret files in the temp dir.
+fn secret_file_prefix() -> String {
+    format!("rustsible-secret-{}-", std::process::id())
+}
+
+/// Checks that pasted text is a PEM or OpenSSH private key rather than a public key or
+/// arbitrary text.
+pub fn validate_private_key_text(text: &str) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Paste the private key text".to_string());
+    }
+    if text.starts_with("ssh-") || text.starts_with("ecdsa-") {
+        return Err("That's a public key — paste the private key instead".to_string());
+    }
+    let first = text.lines().next().unwrap_or_default().trim();
+    let last = text.lines().last().unwrap_or_default().trim();
+    let is_private_header = |line: &str, marker: &str| {
+        line.strip_prefix(marker)
+            .and_then(|rest| rest.strip_suffix("-----"))
+            .is_some_and(|kind| kind.ends_with("PRIVATE KEY"))
+    };
+    if !is_private_header(first, "-----BEGIN ") || !is_private_header(last, "-----END ") {
+        return Err("Expected a key between -----BEGIN … PRIVATE KEY----- and -----END … PRIVATE KEY----- lines".to_string());
+    }
+    if text.lines().count() < 3 {
+        return Err("The private key looks truncated".to_string());
+    }
+    Ok(())
+}
+
+/// Writes `text` to a new owner-only temp file, newline-terminated as ssh expects of keys.
+fn write_secret_file(text: &str) -> Result<PathBuf, String> {
+    let n = NEXT_FILE.fetch_add(1, Ordering::SeqCst);
+    let path = std::env::temp_dir().join(format!("{}{}", secret_file_prefix(), n));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let contents = format!("{}\n", text.trim().replace("\r\n", "\n"));
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Temp file holding a secret for one run; shredded when dropped.
+pub struct TempSecret(PathBuf);
+
+impl TempSecret {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempSecret {
+    fn drop(&mut self) {
+        shred(&self.0);
+    }
+}
+
+/// When the config carries a pasted key, writes it to a temp file and points
+/// `ssh_key_path` at it so the rest of the run treats it like any key file.
+pub fn materialize_inline_key(config: &mut ProvisioningConfig) -> Result<Option<TempSecret>, String> {
+    if !config.use_inline_key {
+        return Ok(None);
+    }
+    validate_private_key_text(&config.inline_private_key)?;
+    let path = write_secret_file(&config.inline_private_key)?;
+    config.ssh_key_path = path.display().to_string();
+    Ok(Some(TempSecret(path)))
+}
+
+/// Writes the vault password to a temp file for `--vault-password-file`, if one is set.
+pub fn materialize_vault_password(config: &ProvisioningConfig) -> Result<Option<TempSecret>, String> {
+    if config.vault_password.is_empty() {
+        return Ok(None);
+    }
+    write_secret_file(&config.vault_password).map(|path| Some(TempSecret(path)))
+}
+
+/// Writes the edited inventory to a temp file for `-i`; it may carry connection passwords.
+pub fn materialize_inventory(config: &ProvisioningConfig) -> Result<Option<TempSecret>, String> {
+    let Some(text) = config.edited_inventory() else { return Ok(None) };
+    write_secret_file(text).map(|path| Some(TempSecret(path)))
+}
+
+/// Overwrites a secret file with zeros before unlinking it.
+fn shred(path: &Path) {
+    if let Ok(len) = fs::metadata(path).map(|m| m.len()) {
+        if let Ok(mut file) = fs::OpenOptions::new().write(true).open(path) {
+            let _ = file.write_all(&vec![0u8; len as usize]).and_then(|_| file.sync_all());
+        }
+    }
+    let _ = fs::remove_file(path);
+}
+
+/// Shreds any temp secret files this process left behind, e.g. after a killed run.
+pub fn shred_leftovers() {
+    let prefix = secret_file_prefix();
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else { return };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            shred(&entry.path());
+        }
+    }
+}
@@ -1,68 +1,141 @@
 use eframe::egui::{self, Color32, Visuals, CornerRadius, Stroke, Margin};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub mod macos_v26_colors {
-    use super::Color32;
+/// Colors for one theme variant of the macOS v26 look.
+pub struct Palette {
+    pub app_bg: Color32,
+    pub sidebar_bg: Color32,
+    pub glass_surface: Color32,
+    pub glass_border: Color32,
+    pub hover_overlay: Color32,
+    pub widget_bg: Color32,
+    pub widget_hover: Color32,
+    pub widget_fg: Color32,
+    pub accent: Color32,
+    pub accent_light: Color32,
+    pub selection_bg: Color32,
+    pub text_bright: Color32,
+    pub text_med: Color32,
+    pub text_low: Color32,
+    pub text_primary: Color32,
+    pub success: Color32,
+    pub warning: Color32,
+    pub error: Color32,
+    pub terminal_bg: Color32,
+}
 
-    // --- Platinum v26 "Onyx Crystal" Palette ---
-    pub const APP_BG: Color32 = Color32::from_rgb(10, 10, 12);
-    pub const SIDEBAR_BG: Color32 = Color32::from_rgb(30, 30, 32);
-    
+// --- Platinum v26 "Onyx Crystal" Palette ---
+pub const DARK: Palette = Palette {
+    app_bg: Color32::from_rgb(10, 10, 12),
+    sidebar_bg: Color32::from_rgb(30, 30, 32),
     // Glassmorphism - Refractive surfaces
-    pub const GLASS_SURFACE: Color32 = Color32::from_rgba_premultiplied(40, 40, 45, 200);
-    pub const GLASS_BORDER: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 15);
-    
+    glass_surface: Color32::from_rgba_premultiplied(40, 40, 45, 200),
+    glass_border: Color32::from_rgba_premultiplied(255, 255, 255, 15),
+    hover_overlay: Color32::from_rgba_premultiplied(255, 255, 255, 10),
+    widget_bg: Color32::from_rgb(50, 50, 55),
+    widget_hover: Color32::from_rgb(60, 60, 65),
+    widget_fg: Color32::from_rgba_premultiplied(255, 255, 255, 15),
     // Accents - "Retina Blue"
-    pub const ACCENT: Color32 = Color32::from_rgb(0, 122, 255);
-    pub const ACCENT_LIGHT: Color32 = Color32::from_rgb(100, 180, 255);
-    pub const SELECTION_BG: Color32 = Color32::from_rgb(0, 88, 208);
-    
-    // Typography
-    pub const TEXT_BRIGHT: Color32 = Color32::from_rgb(255, 255, 255);
-    pub const TEXT_MED: Color32 = Color32::from_rgb(160, 160, 165);
-    pub const TEXT_LOW: Color32 = Color32::from_rgb(100, 100, 105);
-    pub const TEXT_PRIMARY: Color32 = Color32::from_rgb(255, 255, 255);
-
-    // Status
-    pub const SUCCESS: Color32 = Color32::from_rgb(48, 209, 88);
-    pub const WARNING: Color32 = Color32::from_rgb(255, 159, 10);
-    pub const ERROR: Color32 = Color32::from_rgb(255, 69, 58);
-    pub const TERMINAL_BG: Color32 = Color32::from_rgb(5, 5, 5);
+    accent: Color32::from_rgb(0, 122, 255),
+    accent_light: Color32::from_rgb(100, 180, 255),
+    selection_bg: Color32::from_rgb(0, 88, 208),
+    text_bright: Color32::from_rgb(255, 255, 255),
+    text_med: Color32::from_rgb(160, 160, 165),
+    text_low: Color32::from_rgb(100, 100, 105),
+    text_primary: Color32::from_rgb(255, 255, 255),
+    success: Color32::from_rgb(48, 209, 88),
+    warning: Color32::from_rgb(255, 159, 10),
+    error: Color32::from_rgb(255, 69, 58),
+    terminal_bg: Color32::from_rgb(5, 5, 5),
+};
+
+// --- "Porcelain" light variant ---
+pub const LIGHT: Palette = Palette {
+    app_bg: Color32::from_rgb(245, 245, 247),
+    sidebar_bg: Color32::from_rgb(232, 232, 237),
+    glass_surface: Color32::from_rgb(255, 255, 255),
+    glass_border: Color32::from_rgba_premultiplied(0, 0, 0, 20),
+    hover_overlay: Color32::from_rgba_premultiplied(0, 0, 0, 10),
+    widget_bg: Color32::from_rgb(228, 228, 232),
+    widget_hover: Color32::from_rgb(214, 214, 220),
+    widget_fg: Color32::from_rgb(88, 88, 94),
+    accent: Color32::from_rgb(0, 122, 255),
+    accent_light: Color32::from_rgb(0, 95, 204),
+    selection_bg: Color32::from_rgb(179, 215, 255),
+    text_bright: Color32::from_rgb(29, 29, 31),
+    text_med: Color32::from_rgb(88, 88, 94),
+    text_low: Color32::from_rgb(134, 134, 139),
+    text_primary: Color32::from_rgb(29, 29, 31),
+    success: Color32::from_rgb(36, 138, 61),
+    warning: Color32::from_rgb(178, 80, 0),
+    error: Color32::from_rgb(215, 0, 21),
+    terminal_bg: Color32::from_rgb(250, 250, 252),
+};
+
+static LIGHT_MODE: AtomicBool = AtomicBool::new(false);
+static COMPACT: AtomicBool = AtomicBool::new(false);
+
+/// The palette for the theme most recently applied by `setup_macos_v26_style`.
+pub fn palette() -> &'static Palette {
+    if LIGHT_MODE.load(Ordering::Relaxed) { &LIGHT } else { &DARK }
+}
+
+/// Whether the style was last applied in compact mode.
+pub fn is_compact() -> bool {
+    COMPACT.load(Ordering::Relaxed)
 }
 
-pub fn setup_macos_v26_style(ctx: &egui::Context) {
-    let mut visuals = Visuals::dark();
+/// Vertical space between cards.
+pub fn card_gap() -> f32 {
+    if is_compact() { 16.0 } else { 40.0 }
+}
+
+pub fn setup_macos_v26_style(ctx: &egui::Context, light: bool, compact: bool) {
+    LIGHT_MODE.store(light, Ordering::Relaxed);
+    COMPACT.store(compact, Ordering::Relaxed);
+    // Pin egui's own theme so set_visuals/set_style target the variant we're building.
+    ctx.set_theme(if light { egui::Theme::Light } else { egui::Theme::Dark });
+    let p = palette();
+    let mut visuals = if light { Visuals::light() } else { Visuals::dark() };
     
-    visuals.window_fill = macos_v26_colors::APP_BG;
-    visuals.panel_fill = macos_v26_colors::SIDEBAR_BG;
+    visuals.window_fill = p.app_bg;
+    visuals.panel_fill = p.sidebar_bg;
     
     let standard_radius = CornerRadius::same(12);
     
-    visuals.widgets.noninteractive.bg_fill = macos_v26_colors::GLASS_SURFACE;
+    visuals.widgets.noninteractive.bg_fill = p.glass_surface;
     visuals.widgets.noninteractive.corner_radius = standard_radius;
-    visuals.widgets.noninteractive.fg_stroke = Stroke::new(1.0, macos_v26_colors::TEXT_LOW);
+    visuals.widgets.noninteractive.fg_stroke = Stroke::new(1.0, p.text_low);
 
-    visuals.widgets.inactive.bg_fill = Color32::from_rgb(50, 50, 55);
+    visuals.widgets.inactive.bg_fill = p.widget_bg;
     visuals.widgets.inactive.corner_radius = CornerRadius::same(8);
-    visuals.widgets.inactive.fg_stroke = Stroke::new(1.0, macos_v26_colors::GLASS_BORDER);
+    visuals.widgets.inactive.fg_stroke = Stroke::new(1.0, p.widget_fg);
     
-    visuals.widgets.hovered.bg_fill = Color32::from_rgb(60, 60, 65);
+    visuals.widgets.hovered.bg_fill = p.widget_hover;
     visuals.widgets.hovered.corner_radius = CornerRadius::same(8);
-    visuals.widgets.hovered.fg_stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.widgets.hovered.fg_stroke = Stroke::new(1.0, p.text_bright);
     
-    visuals.widgets.active.bg_fill = macos_v26_colors::ACCENT;
+    visuals.widgets.active.bg_fill = p.accent;
     visuals.widgets.active.corner_radius = CornerRadius::same(8);
     visuals.widgets.active.fg_stroke = Stroke::new(1.0, Color32::WHITE);
     
-    visuals.selection.bg_fill = macos_v26_colors::SELECTION_BG;
-    visuals.selection.stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.selection.bg_fill = p.selection_bg;
+    visuals.selection.stroke = Stroke::new(1.0, p.text_bright);
 
     ctx.set_visuals(visuals);
 
     let mut style = (*ctx.style()).clone();
-    style.spacing.item_spacing = egui::vec2(12.0, 12.0);
-    style.spacing.button_padding = egui::vec2(16.0, 8.0);
-    style.spacing.window_margin = Margin::same(24);
-    style.spacing.indent = 24.0;
+    if compact {
+        style.spacing.item_spacing = egui::vec2(8.0, 6.0);
+        style.spacing.button_padding = egui::vec2(10.0, 4.0);
+        style.spacing.window_margin = Margin::same(12);
+        style.spacing.indent = 16.0;
+    } else {
+        style.spacing.item_spacing = egui::vec2(12.0, 12.0);
+        style.spacing.button_padding = egui::vec2(16.0, 8.0);
+        style.spacing.window_margin = Margin::same(24);
+        style.spacing.indent = 24.0;
+    }
     
     ctx.set_style(style);
 }
@@ -70,26 +143,103 @@ pub fn setup_macos_v26_style(ctx: &egui::Context) {
 /// Renders a high-fidelity macOS v26 platinum card
 pub fn crystal_card(ui: &mut egui::Ui, add_contents: impl FnOnce(&mut egui::Ui)) {
     egui::Frame::NONE
-        .fill(macos_v26_colors::GLASS_SURFACE)
+        .fill(palette().glass_surface)
         .corner_radius(CornerRadius::same(16))
-        .stroke(Stroke::new(1.0, macos_v26_colors::GLASS_BORDER))
-        .inner_margin(24.0)
+        .stroke(Stroke::new(1.0, palette().glass_border))
+        .inner_margin(if is_compact() { 12.0 } else { 24.0 })
         .show(ui, add_contents);
 }
 
-pub fn ansible_line_style(line: &str) -> (Color32, bool) {
+/// Compiled `highlight_rules`: the first pattern that matches a line recolors it.
+#[derive(Default)]
+pub struct LineHighlights {
+    source: Vec<(String, [u8; 3])>,
+    rules: Vec<(regex::Regex, Color32)>,
+}
+
+impl LineHighlights {
+    /// Recompiles when the configured rules changed. Blank and invalid patterns are skipped.
+    pub fn update(&mut self, rules: &[(String, [u8; 3])]) {
+        if self.source == rules {
+            return;
+        }
+        self.source = rules.to_vec();
+        self.rules = rules
+            .iter()
+            .filter(|(pattern, _)| !pattern.trim().is_empty())
+            .filter_map(|(pattern, [r, g, b])| regex::Regex::new(pattern).ok().map(|re| (re, Color32::from_rgb(*r, *g, *b))))
+            .collect();
+    }
+
+    /// `ansible_line_style`, with the color of the first matching rule.
+    pub fn line_style(&self, line: &str) -> (Color32, bool) {
+        let (color, bold) = ansible_line_style(line);
+        let color = self.rules.iter().find(|(re, _)| re.is_match(line)).map_or(color, |(_, c)| *c);
+        (color, bold)
+    }
+}
+
+/// Checks that a highlight pattern compiles.
+pub fn validate_highlight_pattern(pattern: &str) -> Result<(), String> {
+    regex::Regex::new(pattern).map(|_| ()).map_err(|e| match e {
+        regex::Error::Syntax(msg) => msg.lines().last().unwrap_or("invalid pattern").trim().to_string(),
+        other => other.to_string(),
+    })
+}
+
+/// What an output line reports, as far as its styling is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Timing,
+    Unreachable,
+    Failure,
+    Warning,
+    Changed,
+    Ok,
+    Header,
+    Other,
+}
+
+impl LineKind {
+    /// Failures, unreachable hosts and warnings.
+    pub fn is_problem(self) -> bool {
+        matches!(self, LineKind::Unreachable | LineKind::Failure | LineKind::Warning)
+    }
+}
+
+pub fn classify_line(line: &str) -> LineKind {
     let trimmed = line.trim();
+    if crate::is_timing_only_line(trimmed) {
+        return LineKind::Timing;
+    }
+    if trimmed.contains("UNREACHABLE!") {
+        return LineKind::Unreachable;
+    }
     if trimmed.contains("FAILED") || trimmed.contains("fatal:") || trimmed.contains("ERROR") {
-        return (macos_v26_colors::ERROR, true);
+        return LineKind::Failure;
+    }
+    if trimmed.starts_with("[WARNING]") || trimmed.starts_with("[DEPRECATION WARNING]") {
+        return LineKind::Warning;
     }
     if trimmed.contains("changed:") {
-        return (macos_v26_colors::WARNING, false);
+        return LineKind::Changed;
     }
     if trimmed.contains("ok:") || trimmed.contains("SUCCESS") {
-        return (macos_v26_colors::SUCCESS, false);
+        return LineKind::Ok;
     }
     if trimmed.starts_with("TASK") || trimmed.starts_with("PLAY") {
-        return (macos_v26_colors::TEXT_PRIMARY, true);
+        return LineKind::Header;
+    }
+    LineKind::Other
+}
+
+pub fn ansible_line_style(line: &str) -> (Color32, bool) {
+    match classify_line(line) {
+        LineKind::Timing => (palette().text_low, false),
+        LineKind::Unreachable | LineKind::Failure => (palette().error, true),
+        LineKind::Warning | LineKind::Changed => (palette().warning, false),
+        LineKind::Ok => (palette().success, false),
+        LineKind::Header => (palette().text_primary, true),
+        LineKind::Other => (palette().text_med, false),
     }
-    (macos_v26_colors::TEXT_MED, false)
 }
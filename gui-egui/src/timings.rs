@@ -0,0 +1,140 @@
+use crate::is_timing_only_line;
+use std::time::Duration;
+
+/// Total time spent in one task, summed over every play and host that ran it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskTiming {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// Task name from a `TASK [name] ****` or `RUNNING HANDLER [name] ****` header.
+fn parse_header(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("TASK [").or_else(|| line.strip_prefix("RUNNING HANDLER ["))?;
+    let end = rest.rfind(']')?;
+    Some(rest[..end].trim())
+}
+
+/// The `(0:00:01.234)` delta of a `profile_tasks` timing line.
+fn parse_delta(line: &str) -> Option<Duration> {
+    let start = line.find('(')?;
+    let end = start + line[start..].find(')')?;
+    let mut parts = line[start + 1..end].split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Per-task durations from `profile_tasks` output, slowest first.
+///
+/// The timing line printed as a task starts carries the time since the previous one
+/// started, so each delta is credited to the task before it. The closing
+/// `name ---- 1.23s` summary is ignored since it's truncated to the slowest few.
+pub fn parse_task_timings<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<TaskTiming> {
+    let mut timings: Vec<TaskTiming> = Vec::new();
+    let mut running: Option<String> = None;
+    let mut next: Option<String> = None;
+    for line in lines {
+        let line = line.trim();
+        if let Some(name) = parse_header(line) {
+            next = Some(name.to_string());
+        } else if line.starts_with("PLAY ") {
+            next = None;
+        } else if is_timing_only_line(line) {
+            if let (Some(name), Some(delta)) = (running.take(), parse_delta(line)) {
+                match timings.iter_mut().find(|t| t.name == name) {
+                    Some(timing) => timing.duration += delta,
+                    None => timings.push(TaskTiming { name, duration: delta }),
+                }
+            }
+            running = next.take();
+        }
+    }
+    timings.sort_by_key(|t| std::cmp::Reverse(t.duration));
+    timings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+PLAY [Provision target] ********************************************************
+
+TASK [Gathering Facts] *********************************************************
+Saturday 17 October 2026  09:14:03 +0000 (0:00:00.034)       0:00:03.512 *******
+ok: [web1]
+ok: [web2]
+
+TASK [common : Install base packages] ******************************************
+Saturday 17 October 2026  09:14:05 +0000 (0:00:02.118)       0:00:05.630 *******
+changed: [web1]
+
+TASK [common : Update apt cache] ***********************************************
+Saturday 17 October 2026  09:15:17 +0000 (0:01:12.250)       0:01:17.880 *******
+changed: [web1]
+
+RUNNING HANDLER [security : restart ssh] ***************************************
+Saturday 17 October 2026  09:15:20 +0000 (0:00:03.000)       0:01:20.880 *******
+changed: [web1]
+
+PLAY [Second play] *************************************************************
+
+TASK [common : Install base packages] ******************************************
+Saturday 17 October 2026  09:15:21 +0000 (0:00:00.500)       0:01:21.380 *******
+ok: [web1]
+
+PLAY RECAP *********************************************************************
+web1 : ok=5 changed=3 unreachable=0 failed=0 skipped=0 rescued=0 ignored=0
+
+Saturday 17 October 2026  09:15:22 +0000 (0:00:01.250)       0:01:22.630 *******
+===============================================================================
+common : Install base packages ------------------------------------------ 73.50s
+security : restart ssh -------------------------------------------------- 0.50s
+";
+
+    fn timing(name: &str, millis: u64) -> TaskTiming {
+        TaskTiming { name: name.to_string(), duration: Duration::from_millis(millis) }
+    }
+
+    #[test]
+    fn credits_each_delta_to_the_previous_task_slowest_first() {
+        assert_eq!(parse_task_timings(SAMPLE.lines()), vec![
+            // 1:12.250 from the first play plus 1.250 from the second.
+            timing("common : Install base packages", 73_500),
+            timing("common : Update apt cache", 3_000),
+            timing("Gathering Facts", 2_118),
+            timing("security : restart ssh", 500),
+        ]);
+    }
+
+    #[test]
+    fn task_header_without_timing_line_is_dropped_at_play_start() {
+        let lines = "\
+TASK [skipped everywhere] ******************************************************
+PLAY [Next] ********************************************************************
+TASK [real task] ***************************************************************
+Saturday 17 October 2026  09:14:03 +0000 (0:00:00.034)       0:00:03.512 *******
+Saturday 17 October 2026  09:14:04 +0000 (0:00:01.000)       0:00:04.512 *******";
+        assert_eq!(parse_task_timings(lines.lines()), vec![timing("real task", 1_000)]);
+    }
+
+    #[test]
+    fn ignores_the_summary_table() {
+        let summary = "===============================================================================\ncommon : Install base packages ------------------------------------------ 73.50s";
+        assert!(parse_task_timings(summary.lines()).is_empty());
+    }
+
+    #[test]
+    fn parses_deltas() {
+        assert_eq!(parse_delta("x (0:00:02.118) 0:00:05.630 ***"), Some(Duration::from_millis(2_118)));
+        assert_eq!(parse_delta("x (1:02:03) 1:02:03 ***"), Some(Duration::from_secs(3_723)));
+        assert_eq!(parse_delta("x (0:00) 0:00:05.630 ***"), None);
+        assert_eq!(parse_delta("x (0:00:-1.0) ***"), None);
+        assert_eq!(parse_delta("no delta here"), None);
+    }
+}